@@ -6,4 +6,8 @@ fn main() {
     if cfg!(feature = "hw_tests") || Path::new("/dev/sev").exists() {
         println!("cargo:rustc-cfg=has_sev");
     }
+
+    if Path::new("/dev/sev-guest").exists() {
+        println!("cargo:rustc-cfg=has_sev_guest");
+    }
 }
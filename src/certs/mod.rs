@@ -7,6 +7,8 @@ pub mod ca;
 mod chain;
 pub mod sev;
 #[cfg(feature = "openssl")]
+pub mod snp;
+#[cfg(feature = "openssl")]
 mod util;
 
 #[cfg(feature = "openssl")]
@@ -217,6 +217,47 @@ impl Verifiable for (&ca::Certificate, &Certificate) {
     }
 }
 
+/// Verifies the signature of an SEV attestation report against a PEK.
+#[cfg(feature = "openssl")]
+impl Verifiable for (&Certificate, &crate::launch::sev::AttestationReport) {
+    type Output = ();
+
+    fn verify(self) -> Result<()> {
+        use crate::launch::sev::AttestationReport;
+
+        let (pek, report) = self;
+        if Usage::try_from(pek)? != Usage::PEK || report.sig_usage != Usage::PEK.0 {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+
+        let key = PublicKey::try_from(pek)?;
+        let hash = match report.sig_algo {
+            0x0002 => hash::MessageDigest::sha256(),
+            0x0102 => hash::MessageDigest::sha384(),
+            _ => return Err(ErrorKind::InvalidInput.into()),
+        };
+
+        if hash != key.hash {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+
+        let mut bytes = Vec::with_capacity(size_of::<AttestationReport>());
+        bytes.save(report)?;
+        let digest = hash::hash(hash, &bytes[..AttestationReport::SIGNED_LEN])?;
+
+        let sig = ecdsa::EcdsaSig::from_private_components(
+            bn::BigNum::from_le(&report.signature.r)?,
+            bn::BigNum::from_le(&report.signature.s)?,
+        )?;
+
+        if sig.verify(&digest, &*key.key.ec_key()?)? {
+            Ok(())
+        } else {
+            Err(ErrorKind::NotFound.into())
+        }
+    }
+}
+
 #[cfg(feature = "openssl")]
 impl Signer<Certificate> for PrivateKey<Usage> {
     type Output = ();
@@ -242,3 +283,45 @@ impl Certificate {
         u32::from_le(unsafe { self.version })
     }
 }
+
+#[cfg(all(test, feature = "openssl"))]
+mod report {
+    use super::*;
+    use crate::launch::sev::{AttestationReport, Policy};
+
+    fn sign(prv: &PrivateKey<Usage>, report: &mut AttestationReport) {
+        let mut bytes = vec![];
+        bytes.save(report).unwrap();
+        let digest = hash::hash(prv.hash, &bytes[..AttestationReport::SIGNED_LEN]).unwrap();
+
+        let sig = ecdsa::EcdsaSig::sign(&digest, &*prv.key.ec_key().unwrap()).unwrap();
+        report.signature.r = sig.r().as_le_bytes();
+        report.signature.s = sig.s().as_le_bytes();
+    }
+
+    #[test]
+    fn verify() {
+        let (pek, prv) = Certificate::generate(Usage::PEK).unwrap();
+
+        let mut bytes = [0u8; size_of::<AttestationReport>()];
+        bytes[..16].copy_from_slice(&[0x11; 16]);
+        bytes[16..48].copy_from_slice(&[0x22; 32]);
+        bytes[0x34..0x38].copy_from_slice(&0x1002u32.to_le_bytes());
+        bytes[0x38..0x3c].copy_from_slice(&0x0002u32.to_le_bytes());
+
+        let mut report: AttestationReport = (&bytes[..]).load().unwrap();
+        assert_eq!(report.policy, Policy::default());
+        sign(&prv, &mut report);
+        (&pek, &report).verify().unwrap();
+
+        let mut tampered = report;
+        tampered.launch_digest[0] ^= 1;
+        assert!((&pek, &tampered).verify().is_err());
+
+        let (other, _) = Certificate::generate(Usage::PEK).unwrap();
+        assert!((&other, &report).verify().is_err());
+
+        let (pdh, _) = Certificate::generate(Usage::PDH).unwrap();
+        assert!((&pdh, &report).verify().is_err());
+    }
+}
@@ -74,3 +74,15 @@ impl<'a> Verifiable for &'a Chain {
         Ok(&self.pdh)
     }
 }
+
+/// Verifies the chain and then the signature of an SEV attestation report
+/// against its PEK.
+#[cfg(feature = "openssl")]
+impl Verifiable for (&Chain, &crate::launch::sev::AttestationReport) {
+    type Output = ();
+
+    fn verify(self) -> Result<()> {
+        self.0.verify()?;
+        (&self.0.pek, self.1).verify()
+    }
+}
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! For operating on the SEV-SNP certificate authority chain.
+
+use super::*;
+
+/// The AMD certificate authority chain for SEV-SNP.
+///
+/// No builtin SEV-SNP certificates are shipped yet. Fetch the chain of a
+/// product (see [`Product::name`](crate::Product::name)) from
+/// `https://kdsintf.amd.com/vcek/v1/{product}/cert_chain`, pin it, and
+/// load it with [`Chain::from_pem_bundle`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chain {
+    /// The AMD Root Key certificate.
+    pub ark: Certificate,
+
+    /// The AMD SEV Key certificate.
+    pub ask: Certificate,
+}
+
+impl Chain {
+    /// Construct the chain from PEM-encoded ARK and ASK certificates.
+    pub fn from_pem(ark: &[u8], ask: &[u8]) -> Result<Self> {
+        Ok(Self {
+            ark: Certificate::from_pem(ark)?,
+            ask: Certificate::from_pem(ask)?,
+        })
+    }
+
+    /// Construct the chain from DER-encoded ARK and ASK certificates.
+    pub fn from_der(ark: &[u8], ask: &[u8]) -> Result<Self> {
+        Ok(Self {
+            ark: Certificate::from_der(ark)?,
+            ask: Certificate::from_der(ask)?,
+        })
+    }
+
+    /// Construct the chain from a bundle of PEM-encoded certificates
+    /// containing the ASK followed by the ARK. This is the format in
+    /// which AMD's Key Distribution Service (KDS) serves the chain.
+    pub fn from_pem_bundle(bundle: &[u8]) -> Result<Self> {
+        let mut certs = X509::stack_from_pem(bundle)?.into_iter();
+
+        match (certs.next(), certs.next(), certs.next()) {
+            (Some(ask), Some(ark), None) => Ok(Self {
+                ark: ark.into(),
+                ask: ask.into(),
+            }),
+            _ => Err(ErrorKind::InvalidInput.into()),
+        }
+    }
+}
+
+impl<'a> Verifiable for &'a Chain {
+    type Output = &'a Certificate;
+
+    fn verify(self) -> Result<Self::Output> {
+        (&self.ark, &self.ark).verify()?;
+        (&self.ark, &self.ask).verify()?;
+        Ok(&self.ask)
+    }
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Utilities for operating on entire SEV-SNP certificate chains.
+
+use super::*;
+
+use crate::firmware::guest::{AttestationReport, SigningKey};
+
+use openssl::{bn::BigNum, ecdsa::EcdsaSig, sha::Sha384};
+
+/// The only signature algorithm currently defined for attestation
+/// reports: ECDSA P-384 with SHA-384.
+const SIG_ALGO_ECDSA_P384_SHA384: u32 = 1;
+
+/// The number of bytes of an attestation report covered by its signature.
+const REPORT_SIGNED_LEN: usize = 0x2A0;
+
+/// A complete SEV-SNP certificate chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chain {
+    /// The certificate authority chain.
+    pub ca: ca::Chain,
+
+    /// The Versioned Chip Endorsement Key certificate.
+    pub vcek: Certificate,
+}
+
+impl Chain {
+    /// Construct the chain from PEM-encoded ARK, ASK and VCEK certificates.
+    pub fn from_pem(ark: &[u8], ask: &[u8], vcek: &[u8]) -> Result<Self> {
+        Ok(Self {
+            ca: ca::Chain::from_pem(ark, ask)?,
+            vcek: Certificate::from_pem(vcek)?,
+        })
+    }
+
+    /// Construct the chain from DER-encoded ARK, ASK and VCEK certificates.
+    pub fn from_der(ark: &[u8], ask: &[u8], vcek: &[u8]) -> Result<Self> {
+        Ok(Self {
+            ca: ca::Chain::from_der(ark, ask)?,
+            vcek: Certificate::from_der(vcek)?,
+        })
+    }
+}
+
+impl<'a> Verifiable for &'a Chain {
+    type Output = &'a Certificate;
+
+    fn verify(self) -> Result<Self::Output> {
+        let ask = self.ca.verify()?;
+        (ask, &self.vcek).verify()?;
+        Ok(&self.vcek)
+    }
+}
+
+/// Verifies the chain and then the signature of the attestation report
+/// against the VCEK.
+impl Verifiable for (&Chain, &AttestationReport) {
+    type Output = ();
+
+    fn verify(self) -> Result<()> {
+        let vcek = self.0.verify()?;
+        let report = self.1;
+
+        // A VLEK-signed report must not be checked against a VCEK.
+        if report.signing_key() != Some(SigningKey::Vcek) {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+
+        if report.sig_algo != SIG_ALGO_ECDSA_P384_SHA384 {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+
+        let mut bytes = Vec::with_capacity(std::mem::size_of::<AttestationReport>());
+        bytes.save(report)?;
+
+        let mut sha = Sha384::new();
+        sha.update(&bytes[..REPORT_SIGNED_LEN]);
+        let digest = sha.finish();
+
+        let sig = EcdsaSig::from_private_components(
+            BigNum::from_le(&report.signature.r)?,
+            BigNum::from_le(&report.signature.s)?,
+        )?;
+
+        let key = vcek.public_key()?.ec_key()?;
+        if sig.verify(&digest, &key)? {
+            Ok(())
+        } else {
+            Err(ErrorKind::NotFound.into())
+        }
+    }
+}
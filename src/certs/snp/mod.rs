@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! For operating on the X.509 certificates of the SEV-SNP chain of trust.
+//!
+//! Unlike the legacy SEV certificates, the SEV-SNP certificates are
+//! standard X.509 certificates: the AMD Root Key (ARK) signs itself and
+//! the AMD SEV Key (ASK), which in turn signs the Versioned Chip
+//! Endorsement Key (VCEK) that signs attestation reports.
+
+pub mod ca;
+mod chain;
+
+pub use chain::Chain;
+
+use super::*;
+
+use openssl::x509::X509;
+
+/// An X.509 certificate of the SEV-SNP chain of trust.
+#[derive(Clone, Debug)]
+pub struct Certificate(X509);
+
+impl Certificate {
+    /// Parse a PEM-encoded certificate.
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        Ok(Self(X509::from_pem(pem)?))
+    }
+
+    /// Parse a DER-encoded certificate.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        Ok(Self(X509::from_der(der)?))
+    }
+
+    /// Serialize the certificate in PEM format.
+    pub fn to_pem(&self) -> Result<Vec<u8>> {
+        Ok(self.0.to_pem()?)
+    }
+
+    /// Serialize the certificate in DER format.
+    pub fn to_der(&self) -> Result<Vec<u8>> {
+        Ok(self.0.to_der()?)
+    }
+
+    /// Get the public key contained in the certificate.
+    pub fn public_key(&self) -> Result<pkey::PKey<pkey::Public>> {
+        Ok(self.0.public_key()?)
+    }
+}
+
+impl From<X509> for Certificate {
+    fn from(x509: X509) -> Self {
+        Self(x509)
+    }
+}
+
+impl From<Certificate> for X509 {
+    fn from(cert: Certificate) -> Self {
+        cert.0
+    }
+}
+
+impl AsRef<X509> for Certificate {
+    fn as_ref(&self) -> &X509 {
+        &self.0
+    }
+}
+
+impl Eq for Certificate {}
+impl PartialEq for Certificate {
+    fn eq(&self, other: &Certificate) -> bool {
+        match (self.0.to_der(), other.0.to_der()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl codicon::Decoder<()> for Certificate {
+    type Error = Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> Result<Self> {
+        let mut der = Vec::new();
+        reader.read_to_end(&mut der)?;
+        Self::from_der(&der)
+    }
+}
+
+impl codicon::Encoder<()> for Certificate {
+    type Error = Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> Result<()> {
+        writer.write_all(&self.to_der()?)
+    }
+}
+
+/// Verifies that the first certificate signed the second one.
+impl Verifiable for (&Certificate, &Certificate) {
+    type Output = ();
+
+    fn verify(self) -> Result<()> {
+        let key = self.0.public_key()?;
+
+        // OpenSSL's verify will return Ok(true) if the signature
+        // is verified and Ok(false) if not.
+        if (self.1).0.verify(&key)? {
+            Ok(())
+        } else {
+            Err(ErrorKind::NotFound.into())
+        }
+    }
+}
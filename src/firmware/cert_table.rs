@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The table of certificates a host provides to SEV-SNP guests along with
+//! extended attestation reports.
+//!
+//! The table starts with an array of entries, each holding the GUID of a
+//! certificate and its offset (from the start of the table) and length.
+//! The array is terminated by an all-zero entry and followed by the
+//! certificates themselves (GHCB specification, Table 8). GUIDs are stored
+//! in RFC 4122 byte order.
+
+use std::convert::{TryFrom, TryInto};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+/// The size of an entry of the table: a GUID, an offset and a length.
+const ENTRY_LEN: usize = 16 + 4 + 4;
+
+/// The kind of a certificate in the table.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CertType {
+    /// The AMD Root Key (c0b406a4-a803-4952-9743-3fb6014cd0ae).
+    Ark,
+
+    /// The AMD SEV Signing Key (4ab7b379-bbac-4fe4-a02f-05aef327c782).
+    Ask,
+
+    /// The Versioned Chip Endorsement Key
+    /// (63da758d-e664-4564-adc5-f4b93be8accd).
+    Vcek,
+
+    /// The Versioned Loaded Endorsement Key
+    /// (a8074bc2-a25a-483e-aae6-39c045a0b8a1).
+    Vlek,
+
+    /// The certificate revocation list
+    /// (92f81bc3-5811-4d3d-97ff-d19f88dc67ea).
+    Crl,
+
+    /// Any other certificate, identified by its GUID.
+    Other([u8; 16]),
+}
+
+impl CertType {
+    const ARK: [u8; 16] = [
+        0xc0, 0xb4, 0x06, 0xa4, 0xa8, 0x03, 0x49, 0x52, 0x97, 0x43, 0x3f, 0xb6, 0x01, 0x4c, 0xd0,
+        0xae,
+    ];
+
+    const ASK: [u8; 16] = [
+        0x4a, 0xb7, 0xb3, 0x79, 0xbb, 0xac, 0x4f, 0xe4, 0xa0, 0x2f, 0x05, 0xae, 0xf3, 0x27, 0xc7,
+        0x82,
+    ];
+
+    const VCEK: [u8; 16] = [
+        0x63, 0xda, 0x75, 0x8d, 0xe6, 0x64, 0x45, 0x64, 0xad, 0xc5, 0xf4, 0xb9, 0x3b, 0xe8, 0xac,
+        0xcd,
+    ];
+
+    const VLEK: [u8; 16] = [
+        0xa8, 0x07, 0x4b, 0xc2, 0xa2, 0x5a, 0x48, 0x3e, 0xaa, 0xe6, 0x39, 0xc0, 0x45, 0xa0, 0xb8,
+        0xa1,
+    ];
+
+    const CRL: [u8; 16] = [
+        0x92, 0xf8, 0x1b, 0xc3, 0x58, 0x11, 0x4d, 0x3d, 0x97, 0xff, 0xd1, 0x9f, 0x88, 0xdc, 0x67,
+        0xea,
+    ];
+
+    /// The GUID identifying this kind of certificate.
+    pub fn guid(&self) -> [u8; 16] {
+        match self {
+            CertType::Ark => Self::ARK,
+            CertType::Ask => Self::ASK,
+            CertType::Vcek => Self::VCEK,
+            CertType::Vlek => Self::VLEK,
+            CertType::Crl => Self::CRL,
+            CertType::Other(guid) => *guid,
+        }
+    }
+}
+
+impl From<[u8; 16]> for CertType {
+    fn from(guid: [u8; 16]) -> Self {
+        match guid {
+            Self::ARK => CertType::Ark,
+            Self::ASK => CertType::Ask,
+            Self::VCEK => CertType::Vcek,
+            Self::VLEK => CertType::Vlek,
+            Self::CRL => CertType::Crl,
+            guid => CertType::Other(guid),
+        }
+    }
+}
+
+/// A certificate in the table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertTableEntry {
+    /// The kind of the certificate.
+    pub cert_type: CertType,
+
+    /// The certificate (usually DER-encoded).
+    pub data: Vec<u8>,
+}
+
+impl CertTableEntry {
+    /// Create an entry for the given certificate.
+    pub fn new(cert_type: CertType, data: Vec<u8>) -> Self {
+        Self { cert_type, data }
+    }
+}
+
+/// The table of certificates returned with extended attestation reports.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CertTable {
+    /// The certificates, in table order.
+    pub entries: Vec<CertTableEntry>,
+}
+
+impl CertTable {
+    /// Create a table of the given certificates.
+    pub fn new(entries: Vec<CertTableEntry>) -> Self {
+        Self { entries }
+    }
+
+    /// The first certificate of the given kind, if any.
+    pub fn get(&self, cert_type: CertType) -> Option<&[u8]> {
+        self.entries
+            .iter()
+            .find(|e| e.cert_type == cert_type)
+            .map(|e| &e.data[..])
+    }
+
+    /// Parse a table from its raw bytes.
+    ///
+    /// Any bytes after the certificates (such as the padding of the page
+    /// the table was returned in) are ignored. An empty or all-zero buffer
+    /// is an empty table.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let invalid = || Error::from(ErrorKind::InvalidData);
+        let mut entries = vec![];
+
+        if bytes.is_empty() {
+            return Ok(Self { entries });
+        }
+
+        for header in bytes.chunks(ENTRY_LEN) {
+            if header.len() != ENTRY_LEN {
+                return Err(invalid());
+            }
+
+            let guid: [u8; 16] = header[..16].try_into().unwrap();
+            let offset = u32::from_le_bytes(header[16..20].try_into().unwrap()) as usize;
+            let length = u32::from_le_bytes(header[20..24].try_into().unwrap()) as usize;
+
+            if guid == [0; 16] && offset == 0 && length == 0 {
+                return Ok(Self { entries });
+            }
+
+            let end = offset.checked_add(length).ok_or_else(invalid)?;
+            let data = bytes.get(offset..end).ok_or_else(invalid)?;
+            entries.push(CertTableEntry::new(guid.into(), data.to_vec()));
+        }
+
+        // The table is missing its terminating entry.
+        Err(invalid())
+    }
+
+    /// Serialize the table: the entries, the terminating entry and the
+    /// certificates in table order.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let too_large = |_| Error::from(ErrorKind::InvalidInput);
+        let mut offset = (self.entries.len() + 1) * ENTRY_LEN;
+        let mut bytes = Vec::with_capacity(offset);
+
+        for entry in &self.entries {
+            let length = entry.data.len();
+            bytes.extend_from_slice(&entry.cert_type.guid());
+            bytes.extend_from_slice(&u32::try_from(offset).map_err(too_large)?.to_le_bytes());
+            bytes.extend_from_slice(&u32::try_from(length).map_err(too_large)?.to_le_bytes());
+            offset += length;
+        }
+
+        bytes.extend_from_slice(&[0; ENTRY_LEN]);
+        for entry in &self.entries {
+            bytes.extend_from_slice(&entry.data);
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl codicon::Decoder<()> for CertTable {
+    type Error = Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> Result<Self> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl codicon::Encoder<()> for CertTable {
+    type Error = Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> Result<()> {
+        writer.write_all(&self.to_bytes()?)
+    }
+}
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! A collection of type-safe ioctl implementations for the AMD SEV-SNP guest
+//! request interface. These ioctls are exported by the Linux kernel.
+
+use crate::firmware::guest::types::*;
+use crate::firmware::{Error, Indeterminate};
+
+use iocuddle::*;
+
+use std::marker::PhantomData;
+
+/// The version of the guest message format understood by the kernel.
+const MSG_VERSION: u8 = 1;
+
+/// The host rejected the request because the certificate buffer is too small.
+const VMM_ERR_INVALID_LEN: u32 = 1;
+
+const SEV: Group = Group::new(b'S');
+
+/// Request an attestation report from the AMD SP.
+pub const SNP_GET_REPORT: Ioctl<WriteRead, &GuestRequest<ReportReq, ReportRsp>> =
+    unsafe { SEV.write_read(0x0) };
+
+/// Request a key derived from a root key from the AMD SP.
+pub const SNP_GET_DERIVED_KEY: Ioctl<WriteRead, &GuestRequest<DerivedKeyReq, DerivedKeyRsp>> =
+    unsafe { SEV.write_read(0x1) };
+
+/// Request an attestation report along with the certificates the host
+/// has made available to the guest.
+///
+/// Unlike the other requests, this is a function: the request borrows the
+/// certificate buffer, which a constant could only accept for `'static`.
+pub fn snp_get_ext_report<'a>(
+) -> Ioctl<WriteRead, &'a GuestRequest<'a, ExtReportReq<'a>, ReportRsp>> {
+    unsafe { SEV.write_read(0x2) }
+}
+
+/// Corresponds to the kernel struct `snp_ext_report_req`.
+#[repr(C)]
+pub struct ExtReportReq<'a> {
+    data: ReportReq,
+    certs_address: u64,
+    certs_len: u32,
+    _phantom: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> ExtReportReq<'a> {
+    pub fn new(data: ReportReq, certs: &'a mut [u8]) -> Self {
+        Self {
+            data,
+            certs_address: certs.as_mut_ptr() as _,
+            certs_len: certs.len() as _,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// This method is only meaningful if called *after* the SNP_GET_EXT_REPORT ioctl fails
+    /// because the certificate buffer is too small. The kernel writes the required length
+    /// back to `ExtReportReq.certs_len`.
+    pub fn certs_len(&self) -> usize {
+        self.certs_len as _
+    }
+}
+
+/// The Rust-flavored, FFI-friendly version of `struct snp_guest_request_ioctl` which is
+/// used to pass arguments to the SEV guest ioctl implementation.
+///
+/// This struct is defined in the Linux kernel: include/uapi/linux/sev-guest.h
+#[repr(C)]
+pub struct GuestRequest<'a, Req, Rsp> {
+    msg_version: u8,
+    req_data: u64,
+    resp_data: u64,
+    exitinfo2: u64,
+    _phantom: PhantomData<(&'a mut Req, &'a mut Rsp)>,
+}
+
+impl<'a, Req, Rsp> GuestRequest<'a, Req, Rsp> {
+    /// Create a guest request whose response will be written to `rsp` by the kernel.
+    ///
+    /// The kernel may also write to `req` (e.g. the certificate length of an
+    /// extended report request).
+    pub fn new(req: &'a mut Req, rsp: &'a mut Rsp) -> Self {
+        Self {
+            msg_version: MSG_VERSION,
+            req_data: req as *mut Req as _,
+            resp_data: rsp as *mut Rsp as _,
+            exitinfo2: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Whether the host rejected the request because a buffer was too small.
+    pub fn is_invalid_len(&self) -> bool {
+        (self.exitinfo2 >> 32) as u32 == VMM_ERR_INVALID_LEN
+    }
+
+    /// encapsulate a `std::io::Error` in an `Indeterminate<Error>`
+    pub fn encapsulate(&self, err: std::io::Error) -> Indeterminate<Error> {
+        let fw_error = self.exitinfo2 as u32;
+
+        if fw_error != 0 {
+            Indeterminate::<Error>::from(fw_error)
+        } else if self.is_invalid_len() {
+            Indeterminate::Known(Error::InvalidLen)
+        } else {
+            Indeterminate::<Error>::from(err)
+        }
+    }
+}
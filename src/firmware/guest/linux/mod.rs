@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Operations for SEV-SNP guests to communicate with the AMD Secure Processor.
+
+mod ioctl;
+
+use std::fs::{File, OpenOptions};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::Path;
+
+use codicon::Decoder;
+
+use super::types::*;
+use super::*;
+use ioctl::*;
+
+/// The number of pages initially offered to the host for the certificates
+/// returned alongside an extended attestation report.
+const EXT_REPORT_CERT_PAGES: usize = 4;
+
+/// A handle to the SEV-SNP guest request interface.
+pub struct GuestFirmware(File);
+
+impl GuestFirmware {
+    /// Create a handle to the SEV-SNP guest request interface.
+    pub fn open() -> std::io::Result<GuestFirmware> {
+        Self::open_path("/dev/sev-guest")
+    }
+
+    /// Create a handle to the SEV-SNP guest request interface exposed
+    /// by the device node at the given path.
+    pub fn open_path(path: impl AsRef<Path>) -> std::io::Result<GuestFirmware> {
+        Ok(GuestFirmware(
+            OpenOptions::new().read(true).write(true).open(path)?,
+        ))
+    }
+
+    /// Request an attestation report from the AMD SP.
+    ///
+    /// The `data` is included verbatim in the report, and `vmpl` selects
+    /// the VMPL the report is generated for.
+    pub fn get_report(
+        &mut self,
+        data: [u8; 64],
+        vmpl: u32,
+    ) -> Result<AttestationReport, Indeterminate<Error>> {
+        let mut req = ReportReq::new(data, vmpl);
+        let mut rsp = ReportRsp::default();
+        let mut cmd = GuestRequest::new(&mut req, &mut rsp);
+
+        SNP_GET_REPORT
+            .ioctl(&mut self.0, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Self::report(&rsp)
+    }
+
+    /// Request a key derived from one of the AMD SP's root keys.
+    pub fn get_derived_key(
+        &mut self,
+        request: &DerivedKeyRequest,
+    ) -> Result<[u8; 32], Indeterminate<Error>> {
+        let mut req = DerivedKeyReq::from(request);
+        let mut rsp = DerivedKeyRsp::default();
+        let mut cmd = GuestRequest::new(&mut req, &mut rsp);
+
+        SNP_GET_DERIVED_KEY
+            .ioctl(&mut self.0, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        if rsp.status != 0 {
+            return Err(rsp.status.into());
+        }
+
+        Ok(rsp.key)
+    }
+
+    /// Request an attestation report along with the certificates the host
+    /// has made available to the guest.
+    ///
+    /// The report and the certificate table are returned.
+    pub fn get_ext_report(
+        &mut self,
+        data: [u8; 64],
+        vmpl: u32,
+    ) -> Result<(AttestationReport, CertTable), Indeterminate<Error>> {
+        let mut certs = vec![0u8; EXT_REPORT_CERT_PAGES * 4096];
+
+        loop {
+            let offered = certs.len();
+            let mut rsp = ReportRsp::default();
+            let mut req = ExtReportReq::new(ReportReq::new(data, vmpl), &mut certs);
+            let mut cmd = GuestRequest::new(&mut req, &mut rsp);
+
+            if let Err(e) = snp_get_ext_report().ioctl(&mut self.0, &mut cmd) {
+                let invalid_len = cmd.is_invalid_len();
+                let err = cmd.encapsulate(e);
+
+                // The host has more certificates than we offered room for and
+                // wrote back the required length; try again with a larger buffer.
+                // `cmd` is no longer used, so its borrow of `req` has ended.
+                let required = req.certs_len();
+                if invalid_len && required > offered {
+                    certs = vec![0u8; required];
+                    continue;
+                }
+
+                return Err(err);
+            }
+
+            let report = Self::report(&rsp)?;
+            return Ok((report, CertTable::from_bytes(&certs)?));
+        }
+    }
+
+    fn report(rsp: &ReportRsp) -> Result<AttestationReport, Indeterminate<Error>> {
+        if rsp.status != 0 {
+            return Err(rsp.status.into());
+        }
+
+        match rsp.report() {
+            Some(report) => Ok(AttestationReport::decode(report, ())?),
+            None => Err(Indeterminate::Known(Error::InvalidLen)),
+        }
+    }
+}
+
+impl AsRawFd for GuestFirmware {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The messages exchanged between an SEV-SNP guest and the AMD SP.
+//!
+//! Each message is a header followed by a payload encrypted with
+//! AES-256-GCM under one of the VMPCKs of the guest (see
+//! [`SecretsPage::vmpck`](super::SecretsPage::vmpck)). The IV is the
+//! sequence number of the message and bytes 0x30 to 0x5F of the header are
+//! authenticated along with the payload. The layout is the one of the
+//! `snp_guest_msg` structure of the Linux kernel.
+
+use crate::util::*;
+
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::mem::size_of;
+
+#[cfg(feature = "openssl")]
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+
+/// The maximum size of a message, header included.
+pub const MAX_MESSAGE_SIZE: usize = 4096;
+
+/// The maximum size of the payload of a message.
+pub const MAX_PAYLOAD_SIZE: usize = MAX_MESSAGE_SIZE - size_of::<MessageHeader>();
+
+/// The AES-256-GCM algorithm.
+const ALGO_AES_256_GCM: u8 = 1;
+
+/// The version of the message header format.
+const HDR_VERSION: u8 = 1;
+
+/// The offset of the authenticated fields of the header.
+#[cfg(feature = "openssl")]
+const AAD_OFFSET: usize = 0x30;
+
+/// The length of the AES-256-GCM authentication tag.
+#[cfg(feature = "openssl")]
+const AUTHTAG_LEN: usize = 16;
+
+/// The length of the AES-256-GCM IV.
+#[cfg(feature = "openssl")]
+const IV_LEN: usize = 12;
+
+/// The type of a message.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum MessageType {
+    /// Request to check CPUID values.
+    CpuidReq = 1,
+
+    /// Response to a `CpuidReq`.
+    CpuidRsp = 2,
+
+    /// Request for a derived key.
+    KeyReq = 3,
+
+    /// Response to a `KeyReq`.
+    KeyRsp = 4,
+
+    /// Request for an attestation report.
+    ReportReq = 5,
+
+    /// Response to a `ReportReq`.
+    ReportRsp = 6,
+
+    /// Request from a migration agent to export a guest.
+    ExportReq = 7,
+
+    /// Response to an `ExportReq`.
+    ExportRsp = 8,
+
+    /// Request from a migration agent to import a guest.
+    ImportReq = 9,
+
+    /// Response to an `ImportReq`.
+    ImportRsp = 10,
+
+    /// Request from a migration agent to absorb a guest.
+    AbsorbReq = 11,
+
+    /// Response to an `AbsorbReq`.
+    AbsorbRsp = 12,
+
+    /// Request from a migration agent for the VMRK of a guest.
+    VmrkReq = 13,
+
+    /// Response to a `VmrkReq`.
+    VmrkRsp = 14,
+
+    /// Request to absorb a guest launched without a migration agent.
+    AbsorbNomaReq = 15,
+
+    /// Response to an `AbsorbNomaReq`.
+    AbsorbNomaRsp = 16,
+
+    /// Request for the TSC information of a guest using Secure TSC.
+    TscInfoReq = 17,
+
+    /// Response to a `TscInfoReq`.
+    TscInfoRsp = 18,
+}
+
+impl MessageType {
+    /// The type of the response to this request, or `None` if this is a
+    /// response.
+    pub fn response(self) -> Option<Self> {
+        if self as u8 % 2 == 1 {
+            Self::try_from(self as u8 + 1).ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl TryFrom<u8> for MessageType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            1 => MessageType::CpuidReq,
+            2 => MessageType::CpuidRsp,
+            3 => MessageType::KeyReq,
+            4 => MessageType::KeyRsp,
+            5 => MessageType::ReportReq,
+            6 => MessageType::ReportRsp,
+            7 => MessageType::ExportReq,
+            8 => MessageType::ExportRsp,
+            9 => MessageType::ImportReq,
+            10 => MessageType::ImportRsp,
+            11 => MessageType::AbsorbReq,
+            12 => MessageType::AbsorbRsp,
+            13 => MessageType::VmrkReq,
+            14 => MessageType::VmrkRsp,
+            15 => MessageType::AbsorbNomaReq,
+            16 => MessageType::AbsorbNomaRsp,
+            17 => MessageType::TscInfoReq,
+            18 => MessageType::TscInfoRsp,
+            _ => return Err(ErrorKind::InvalidData.into()),
+        })
+    }
+}
+
+/// The header of a message.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MessageHeader {
+    /// The authentication tag of the message (0x00).
+    pub authtag: [u8; 32],
+
+    /// The sequence number of the message (0x20).
+    pub msg_seqno: u64,
+
+    _reserved_0: [u8; 8],
+
+    /// The algorithm the payload is encrypted with (0x30).
+    pub algo: u8,
+
+    /// The version of the header format (0x31).
+    pub hdr_version: u8,
+
+    /// The size of the header (0x32).
+    pub hdr_size: u16,
+
+    /// The type of the message (0x34).
+    pub msg_type: u8,
+
+    /// The version of the message format (0x35).
+    pub msg_version: u8,
+
+    /// The size of the payload (0x36).
+    pub msg_size: u16,
+
+    _reserved_1: u32,
+
+    /// The VMPL of the VMPCK the payload is encrypted with (0x3C).
+    pub msg_vmpck: u8,
+
+    _reserved_2: [u8; 35],
+}
+
+impl Default for MessageHeader {
+    fn default() -> Self {
+        // All fields are integers (or arrays of them) for which zero is valid.
+        unsafe { std::mem::zeroed() }
+    }
+}
+
+impl MessageHeader {
+    /// Create the header of a message of the given type and payload size.
+    pub fn new(
+        msg_type: MessageType,
+        msg_version: u8,
+        msg_seqno: u64,
+        msg_vmpck: u8,
+        msg_size: usize,
+    ) -> Result<Self> {
+        if msg_size > MAX_PAYLOAD_SIZE {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+
+        Ok(Self {
+            msg_seqno,
+            algo: ALGO_AES_256_GCM,
+            hdr_version: HDR_VERSION,
+            hdr_size: size_of::<Self>() as u16,
+            msg_type: msg_type as u8,
+            msg_version,
+            msg_size: msg_size as u16,
+            msg_vmpck,
+            ..Default::default()
+        })
+    }
+
+    /// The type of the message.
+    pub fn message_type(&self) -> Result<MessageType> {
+        MessageType::try_from(self.msg_type)
+    }
+
+    /// The bytes of the header that are authenticated with the payload.
+    #[cfg(feature = "openssl")]
+    fn aad(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(size_of::<Self>());
+        bytes.save(self)?;
+        Ok(bytes.split_off(AAD_OFFSET))
+    }
+
+    /// The IV of the message: its sequence number, padded with zeroes.
+    #[cfg(feature = "openssl")]
+    fn iv(&self) -> [u8; IV_LEN] {
+        let mut iv = [0u8; IV_LEN];
+        iv[..8].copy_from_slice(&self.msg_seqno.to_le_bytes());
+        iv
+    }
+}
+
+impl codicon::Decoder<()> for MessageHeader {
+    type Error = std::io::Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> Result<Self> {
+        reader.load()
+    }
+}
+
+impl codicon::Encoder<()> for MessageHeader {
+    type Error = std::io::Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> Result<()> {
+        writer.save(self)
+    }
+}
+
+/// An encrypted message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    /// The header of the message.
+    pub header: MessageHeader,
+
+    /// The encrypted payload.
+    pub payload: Vec<u8>,
+}
+
+#[cfg(feature = "openssl")]
+impl Message {
+    /// Encrypt a payload with the VMPCK `key` for the given VMPL.
+    ///
+    /// Each message must use a new sequence number: the AMD SP expects
+    /// the sequence number of a request to follow the one of the previous
+    /// response and answers with the sequence number of the request plus
+    /// one.
+    pub fn seal(
+        key: &[u8; 32],
+        msg_type: MessageType,
+        msg_version: u8,
+        msg_seqno: u64,
+        msg_vmpck: u8,
+        plaintext: &[u8],
+    ) -> Result<Self> {
+        let mut header =
+            MessageHeader::new(msg_type, msg_version, msg_seqno, msg_vmpck, plaintext.len())?;
+
+        let mut tag = [0u8; AUTHTAG_LEN];
+        let payload = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            key,
+            Some(&header.iv()),
+            &header.aad()?,
+            plaintext,
+            &mut tag,
+        )?;
+
+        header.authtag[..AUTHTAG_LEN].copy_from_slice(&tag);
+        Ok(Self { header, payload })
+    }
+
+    /// Decrypt the payload with the VMPCK `key`.
+    ///
+    /// Fails if the header is not one of an AES-256-GCM message or the
+    /// message fails to authenticate.
+    pub fn open(&self, key: &[u8; 32]) -> Result<Vec<u8>> {
+        let header = &self.header;
+        if header.algo != ALGO_AES_256_GCM
+            || header.hdr_version != HDR_VERSION
+            || header.hdr_size as usize != size_of::<MessageHeader>()
+            || header.msg_size as usize != self.payload.len()
+        {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        decrypt_aead(
+            Cipher::aes_256_gcm(),
+            key,
+            Some(&header.iv()),
+            &header.aad()?,
+            &self.payload,
+            &header.authtag[..AUTHTAG_LEN],
+        )
+        .map_err(|_| ErrorKind::InvalidData.into())
+    }
+}
+
+impl codicon::Decoder<()> for Message {
+    type Error = std::io::Error;
+
+    /// Decode a message, ignoring any bytes after its payload (such as the
+    /// rest of the page it was exchanged in).
+    fn decode(mut reader: impl Read, _: ()) -> Result<Self> {
+        let header: MessageHeader = reader.load()?;
+        if header.msg_size as usize > MAX_PAYLOAD_SIZE {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        let mut payload = vec![0u8; header.msg_size as usize];
+        reader.read_exact(&mut payload)?;
+        Ok(Self { header, payload })
+    }
+}
+
+impl codicon::Encoder<()> for Message {
+    type Error = std::io::Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> Result<()> {
+        writer.save(&self.header)?;
+        writer.write_all(&self.payload)
+    }
+}
@@ -0,0 +1,292 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Operations for SEV-SNP guests to communicate with the AMD Secure Processor.
+//!
+//! The Linux kernel exposes the SNP guest request interface through the
+//! `/dev/sev-guest` device node. This module offers a type-safe way to
+//! request attestation reports and derived keys from within a guest, and
+//! to parse the attestation reports the AMD SP produces.
+
+#[cfg(target_os = "linux")]
+mod linux;
+pub mod message;
+mod secrets;
+mod types;
+
+use super::*;
+
+#[cfg(target_os = "linux")]
+pub use linux::GuestFirmware;
+pub use secrets::{SecretsPage, VMPCK_LEN};
+
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+
+/// The root key from which a derived key is generated.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum RootKey {
+    /// The Versioned Chip Endorsement Key.
+    Vcek = 0,
+
+    /// The Virtual Machine Root Key.
+    Vmrk = 1,
+}
+
+bitflags! {
+    /// Guest data which is mixed into a derived key.
+    #[derive(Default)]
+    pub struct GuestFieldSelect: u64 {
+        /// Mix in the guest policy.
+        const POLICY      = 1 << 0;
+
+        /// Mix in the image ID provided at launch.
+        const IMAGE_ID    = 1 << 1;
+
+        /// Mix in the family ID provided at launch.
+        const FAMILY_ID   = 1 << 2;
+
+        /// Mix in the launch measurement.
+        const MEASUREMENT = 1 << 3;
+
+        /// Mix in the guest SVN.
+        const GUEST_SVN   = 1 << 4;
+
+        /// Mix in the TCB version.
+        const TCB_VERSION = 1 << 5;
+    }
+}
+
+/// Parameters for requesting a key derived from a root key.
+///
+/// (Chapter 7.2; Table 18)
+#[derive(Clone, Debug, PartialEq)]
+pub struct DerivedKeyRequest {
+    /// The root key to derive from.
+    pub root_key: RootKey,
+
+    /// The guest data to mix into the derived key.
+    pub fields: GuestFieldSelect,
+
+    /// The VMPL to mix into the derived key. Must be greater than or
+    /// equal to the VMPL of the requesting guest.
+    pub vmpl: u32,
+
+    /// The guest SVN to mix into the derived key. Must not exceed the
+    /// guest SVN provided at launch.
+    pub guest_svn: u32,
+
+    /// The TCB version to mix into the derived key. Must not exceed the
+    /// committed TCB.
+    pub tcb_version: TcbVersion,
+}
+
+bitflags! {
+    /// Information about the platform on which an attestation report
+    /// was generated.
+    #[derive(Default, Deserialize, Serialize)]
+    pub struct PlatformInfo: u64 {
+        /// Indicates that SMT is enabled in the system.
+        const SMT_EN               = 1 << 0;
+
+        /// Indicates that TSME is enabled in the system.
+        const TSME_EN              = 1 << 1;
+
+        /// Indicates that the platform is using error correcting codes
+        /// for memory.
+        const ECC_EN               = 1 << 2;
+
+        /// Indicates that the RAPL feature is disabled.
+        const RAPL_DIS             = 1 << 3;
+
+        /// Indicates that ciphertext hiding is enabled.
+        const CIPHERTEXT_HIDING_EN = 1 << 4;
+    }
+}
+
+/// An ECDSA P-384 signature, as found in attestation reports and in the
+/// authentication information of ID blocks.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Signature {
+    /// The R component of the ECDSA signature, in little-endian.
+    #[serde(with = "crate::util::array")]
+    pub r: [u8; 72],
+
+    /// The S component of the ECDSA signature, in little-endian.
+    #[serde(with = "crate::util::array")]
+    pub s: [u8; 72],
+
+    #[serde(with = "crate::util::array")]
+    _reserved: [u8; 368],
+}
+
+impl Default for Signature {
+    fn default() -> Self {
+        Self {
+            r: [0u8; 72],
+            s: [0u8; 72],
+            _reserved: [0u8; 368],
+        }
+    }
+}
+
+/// An attestation report generated by the AMD SP on behalf of a guest.
+///
+/// (Chapter 7.3; Table 21)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AttestationReport {
+    /// The version of the attestation report format.
+    pub version: u32,
+
+    /// The guest SVN.
+    pub guest_svn: u32,
+
+    /// The guest policy.
+    pub policy: u64,
+
+    /// The family ID provided at launch.
+    pub family_id: [u8; 16],
+
+    /// The image ID provided at launch.
+    pub image_id: [u8; 16],
+
+    /// The VMPL the report was requested for.
+    pub vmpl: u32,
+
+    /// The algorithm used to sign the report.
+    pub sig_algo: u32,
+
+    /// The current TCB of the platform.
+    pub current_tcb: TcbVersion,
+
+    /// Information about the platform.
+    pub plat_info: PlatformInfo,
+
+    /// Whether an author key is present (bit 0), whether the chip ID is
+    /// masked (bit 1), and the key used to sign the report (bits 4:2).
+    pub key_info: u32,
+
+    _reserved_0: u32,
+
+    /// Guest-provided data.
+    #[serde(with = "crate::util::array")]
+    pub report_data: [u8; 64],
+
+    /// The measurement calculated at launch.
+    #[serde(with = "crate::util::array")]
+    pub measurement: [u8; 48],
+
+    /// Data provided by the hypervisor at launch.
+    pub host_data: [u8; 32],
+
+    /// SHA-384 digest of the ID public key that signed the ID block
+    /// provided at launch.
+    #[serde(with = "crate::util::array")]
+    pub id_key_digest: [u8; 48],
+
+    /// SHA-384 digest of the author public key that certified the ID key,
+    /// if any.
+    #[serde(with = "crate::util::array")]
+    pub author_key_digest: [u8; 48],
+
+    /// The report ID of this guest.
+    pub report_id: [u8; 32],
+
+    /// The report ID of this guest's migration agent.
+    pub report_id_ma: [u8; 32],
+
+    /// The TCB version used to derive the VCEK that signed the report.
+    pub reported_tcb: TcbVersion,
+
+    _reserved_1: [u8; 24],
+
+    /// An identifier unique to the chip, or zeroes if masked.
+    #[serde(with = "crate::util::array")]
+    pub chip_id: [u8; 64],
+
+    /// The committed TCB of the platform.
+    pub committed_tcb: TcbVersion,
+
+    /// The build number of the current firmware.
+    pub current_build: u8,
+
+    /// The minor version of the current firmware.
+    pub current_minor: u8,
+
+    /// The major version of the current firmware.
+    pub current_major: u8,
+
+    _reserved_2: u8,
+
+    /// The build number of the committed firmware.
+    pub committed_build: u8,
+
+    /// The minor version of the committed firmware.
+    pub committed_minor: u8,
+
+    /// The major version of the committed firmware.
+    pub committed_major: u8,
+
+    _reserved_3: u8,
+
+    /// The current TCB at the time the guest was launched or imported.
+    pub launch_tcb: TcbVersion,
+
+    #[serde(with = "crate::util::array")]
+    _reserved_4: [u8; 168],
+
+    /// The signature over bytes 0x0 through 0x29F of the report.
+    pub signature: Signature,
+}
+
+/// The key used to sign an attestation report.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SigningKey {
+    /// The Versioned Chip Endorsement Key.
+    Vcek,
+
+    /// The Versioned Loaded Endorsement Key.
+    Vlek,
+
+    /// The report is not signed.
+    Unsigned,
+}
+
+impl AttestationReport {
+    /// The policy of the guest, decoded from `policy`.
+    pub fn guest_policy(&self) -> std::io::Result<crate::launch::snp::Policy> {
+        use std::convert::TryFrom;
+
+        crate::launch::snp::Policy::try_from(self.policy)
+    }
+
+    /// The key used to sign the report, as read from `key_info`.
+    ///
+    /// Returns `None` if the field holds a reserved value.
+    pub fn signing_key(&self) -> Option<SigningKey> {
+        match (self.key_info >> 2) & 0b111 {
+            0 => Some(SigningKey::Vcek),
+            1 => Some(SigningKey::Vlek),
+            7 => Some(SigningKey::Unsigned),
+            _ => None,
+        }
+    }
+}
+
+impl codicon::Decoder<()> for AttestationReport {
+    type Error = std::io::Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> std::io::Result<Self> {
+        reader.load()
+    }
+}
+
+impl codicon::Encoder<()> for AttestationReport {
+    type Error = std::io::Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> std::io::Result<()> {
+        writer.save(self)
+    }
+}
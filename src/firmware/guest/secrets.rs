@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The secrets page the AMD SP populates for an SEV-SNP guest.
+//!
+//! The hypervisor inserts a page of type [`PageType::Secrets`] while
+//! launching the guest and the firmware fills it with the VM platform
+//! communication keys (VMPCKs), among other information. The layout is
+//! the one of the `snp_secrets_page` structure of the Linux kernel.
+//!
+//! [`PageType::Secrets`]: crate::launch::snp::PageType::Secrets
+
+use std::io::{Read, Result};
+use std::mem::size_of;
+use std::ptr::write_volatile;
+use std::slice::from_raw_parts_mut;
+
+/// The length of a VM platform communication key.
+pub const VMPCK_LEN: usize = 32;
+
+/// The SEV-SNP secrets page.
+///
+/// The VMPCKs are zeroed when the page is dropped. The page cannot be
+/// cloned, so that the keys are not copied unnoticed.
+#[repr(C)]
+pub struct SecretsPage {
+    /// The version of the secrets page format (0x00).
+    pub version: u32,
+
+    /// Bit 0 indicates an IMI guest (0x04).
+    imien: u32,
+
+    /// The family, model and stepping of the CPU, as reported by CPUID
+    /// function 1 (0x08).
+    pub fms: u32,
+
+    _reserved_0: u32,
+
+    /// The guest OS visible workarounds, as provided by the hypervisor at
+    /// launch (0x10).
+    pub gosvw: [u8; 16],
+
+    /// The VMPCKs for VMPL0 to VMPL3 (0x20).
+    vmpck: [[u8; VMPCK_LEN]; 4],
+
+    /// An area reserved for use by the guest OS (0xA0). Linux keeps the
+    /// message sequence numbers of the VMPCKs and the AP jump table
+    /// address here.
+    pub os_area: [u8; 96],
+
+    /// A bitmap indicating which quadwords of the VMSA are tweaked (0x100).
+    pub vmsa_tweak_bitmap: [u8; 64],
+
+    /// The guest physical address of the SVSM (0x140).
+    pub svsm_base: u64,
+
+    /// The size of the SVSM (0x148).
+    pub svsm_size: u64,
+
+    /// The guest physical address of the SVSM calling area (0x150).
+    pub svsm_caa: u64,
+
+    /// The maximum SVSM protocol version supported (0x158).
+    pub svsm_max_version: u32,
+
+    /// The VMPL the guest runs at under an SVSM (0x15C).
+    pub svsm_guest_vmpl: u8,
+
+    _reserved_1: [u8; 3],
+
+    /// The percentage decrease from the nominal to the mean TSC frequency,
+    /// for guests using Secure TSC (0x160).
+    pub tsc_factor: u32,
+
+    _reserved_2: [u8; 3740],
+}
+
+impl SecretsPage {
+    /// Whether the guest is an IMI guest (one launched with the help of a
+    /// migration agent).
+    pub fn imi_en(&self) -> bool {
+        self.imien & 1 != 0
+    }
+
+    /// The VMPCK for the given VMPL, if the VMPL exists.
+    pub fn vmpck(&self, vmpl: u32) -> Option<&[u8; VMPCK_LEN]> {
+        self.vmpck.get(vmpl as usize)
+    }
+}
+
+impl Default for SecretsPage {
+    fn default() -> Self {
+        // All fields are integers (or arrays of them) for which zero is valid.
+        unsafe { std::mem::zeroed() }
+    }
+}
+
+impl Drop for SecretsPage {
+    fn drop(&mut self) {
+        for b in self.vmpck.iter_mut().flat_map(|k| k.iter_mut()) {
+            unsafe {
+                write_volatile(b as *mut u8, 0u8);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for SecretsPage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretsPage")
+            .field("version", &self.version)
+            .field("imi_en", &self.imi_en())
+            .field("fms", &self.fms)
+            .field("gosvw", &self.gosvw)
+            .field("vmpck", &"<redacted>")
+            .field("svsm_base", &self.svsm_base)
+            .field("svsm_size", &self.svsm_size)
+            .field("svsm_caa", &self.svsm_caa)
+            .field("svsm_max_version", &self.svsm_max_version)
+            .field("svsm_guest_vmpl", &self.svsm_guest_vmpl)
+            .field("tsc_factor", &self.tsc_factor)
+            .finish()
+    }
+}
+
+impl codicon::Decoder<()> for SecretsPage {
+    type Error = std::io::Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> Result<Self> {
+        // Read straight into the page so the keys are not left behind in
+        // an intermediate buffer.
+        let mut page = Self::default();
+        let p = &mut page as *mut Self as *mut u8;
+        reader.read_exact(unsafe { from_raw_parts_mut(p, size_of::<Self>()) })?;
+        Ok(page)
+    }
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+/// Request an attestation report.
+///
+/// (Chapter 7.3; Table 20)
+#[repr(C)]
+pub struct ReportReq {
+    report_data: [u8; 64],
+    vmpl: u32,
+    _reserved: [u8; 28],
+}
+
+impl ReportReq {
+    pub fn new(report_data: [u8; 64], vmpl: u32) -> Self {
+        Self {
+            report_data,
+            vmpl,
+            _reserved: [0u8; 28],
+        }
+    }
+}
+
+/// The response to an attestation report request.
+///
+/// The Linux kernel always copies 4000 bytes of response data back
+/// to userspace, so the buffer is sized accordingly.
+///
+/// (Chapter 7.3)
+#[repr(C)]
+pub struct ReportRsp {
+    pub status: u32,
+    report_size: u32,
+    _reserved: [u8; 24],
+    report: [u8; 3968],
+}
+
+impl Default for ReportRsp {
+    fn default() -> Self {
+        Self {
+            status: 0,
+            report_size: 0,
+            _reserved: [0u8; 24],
+            report: [0u8; 3968],
+        }
+    }
+}
+
+impl ReportRsp {
+    /// The report bytes, or `None` if the firmware claims a size that
+    /// does not fit into the response buffer.
+    pub fn report(&self) -> Option<&[u8]> {
+        self.report.get(..self.report_size as usize)
+    }
+}
+
+/// Request a key derived from a root key.
+///
+/// (Chapter 7.2; Table 18)
+#[repr(C)]
+pub struct DerivedKeyReq {
+    root_key_select: u32,
+    _reserved: u32,
+    guest_field_select: u64,
+    vmpl: u32,
+    guest_svn: u32,
+    tcb_version: TcbVersion,
+}
+
+impl From<&DerivedKeyRequest> for DerivedKeyReq {
+    fn from(request: &DerivedKeyRequest) -> Self {
+        Self {
+            root_key_select: request.root_key as _,
+            _reserved: 0,
+            guest_field_select: request.fields.bits(),
+            vmpl: request.vmpl,
+            guest_svn: request.guest_svn,
+            tcb_version: request.tcb_version,
+        }
+    }
+}
+
+/// The response to a derived key request.
+///
+/// (Chapter 7.2; Table 19)
+#[derive(Default)]
+#[repr(C)]
+pub struct DerivedKeyRsp {
+    pub status: u32,
+    _reserved: [u8; 28],
+    pub key: [u8; 32],
+}
@@ -24,6 +24,9 @@ impl_const_id! {
     GetId<'_> = 8, /* GET_ID2 is 8, the deprecated GET_ID ioctl is 7 */
 
     SnpPlatformStatus = 9,
+    SnpCommit = 10,
+    SnpConfig = 11,
+    SnpVlekLoad<'_> = 12,
 }
 
 const SEV: Group = Group::new(b'S');
@@ -59,6 +62,15 @@ pub const GET_ID: Ioctl<WriteRead, &Command<GetId<'_>>> = unsafe { SEV.write_rea
 pub const SNP_PLATFORM_STATUS: Ioctl<WriteRead, &Command<SnpPlatformStatus>> =
     unsafe { SEV.write_read(0) };
 
+/// Commit the current SEV-SNP firmware and raise the committed TCB.
+pub const SNP_COMMIT: Ioctl<WriteRead, &Command<SnpCommit>> = unsafe { SEV.write_read(0) };
+
+/// Set the system-wide configuration of the SEV-SNP platform.
+pub const SNP_SET_CONFIG: Ioctl<WriteRead, &Command<SnpConfig>> = unsafe { SEV.write_read(0) };
+
+/// Load a wrapped VLEK hashstick into the SEV-SNP platform.
+pub const SNP_VLEK_LOAD: Ioctl<WriteRead, &Command<SnpVlekLoad<'_>>> = unsafe { SEV.write_read(0) };
+
 /// The Rust-flavored, FFI-friendly version of `struct sev_issue_cmd` which is
 /// used to pass arguments to the SEV ioctl implementation.
 ///
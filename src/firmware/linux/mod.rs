@@ -146,6 +146,33 @@ impl Firmware {
             },
         })
     }
+
+    /// Commit the currently installed SEV-SNP firmware.
+    ///
+    /// This raises the committed TCB to the current TCB, so the platform
+    /// can no longer be rolled back to older firmware.
+    pub fn snp_commit(&mut self) -> Result<(), Indeterminate<Error>> {
+        SNP_COMMIT.ioctl(&mut self.0, &mut Command::from(&SnpCommit))?;
+        Ok(())
+    }
+
+    /// Set the reported TCB and the chip masking options of the SEV-SNP
+    /// platform.
+    pub fn snp_set_config(&mut self, config: &SnpConfig) -> Result<(), Indeterminate<Error>> {
+        SNP_SET_CONFIG.ioctl(&mut self.0, &mut Command::from(config))?;
+        Ok(())
+    }
+
+    /// Load a wrapped VLEK hashstick, so that guest attestation reports are
+    /// signed with the VLEK instead of the VCEK.
+    pub fn snp_vlek_load(
+        &mut self,
+        hashstick: &WrappedVlekHashstick,
+    ) -> Result<(), Indeterminate<Error>> {
+        let vlek_load = SnpVlekLoad::new(hashstick);
+        SNP_VLEK_LOAD.ioctl(&mut self.0, &mut Command::from(&vlek_load))?;
+        Ok(())
+    }
 }
 
 impl AsRawFd for Firmware {
@@ -2,6 +2,8 @@
 
 //! Operations for managing the SEV platform.
 
+mod cert_table;
+pub mod guest;
 #[cfg(target_os = "linux")]
 mod linux;
 mod types;
@@ -13,7 +15,8 @@ use std::{error, io};
 #[cfg(target_os = "linux")]
 pub use linux::Firmware;
 
-pub use types::{PlatformStatusFlags, TcbVersion};
+pub use cert_table::{CertTable, CertTableEntry, CertType};
+pub use types::{PlatformStatusFlags, SnpConfig, SnpConfigMask, TcbVersion, WrappedVlekHashstick};
 
 /// There are a number of error conditions that can occur between this
 /// layer all the way down to the SEV platform. Most of these cases have
@@ -113,6 +116,49 @@ pub enum Error {
 
     /// The SEV platform observed a failed integrity check.
     SecureDataInvalid,
+
+    /// The RMP page size is incorrect.
+    InvalidPageSize,
+
+    /// The RMP page state is incorrect.
+    InvalidPageState,
+
+    /// The metadata entry is invalid.
+    InvalidMdataEntry,
+
+    /// The page ownership is incorrect.
+    InvalidPageOwner,
+
+    /// The AEAD algorithm would have overflowed.
+    AeadOverflow,
+
+    /// A Mailbox mode command was sent while the SEV firmware was in Ring
+    /// Buffer mode.
+    RingBufferExit,
+
+    /// The RMP must be reinitialized.
+    RmpInitRequired,
+
+    /// The SVN of the provided image is lower than the committed SVN.
+    BadSvn,
+
+    /// The firmware version is not compatible with this command.
+    BadVersion,
+
+    /// The firmware must be shut down before this command can be issued.
+    ShutdownRequired,
+
+    /// The firmware update failed.
+    UpdateFailed,
+
+    /// The firmware state must be restored after a failed update.
+    RestoreRequired,
+
+    /// The RMP initialization failed.
+    RmpInitFailed,
+
+    /// The key requested is invalid, unavailable or of the wrong type.
+    InvalidKey,
 }
 
 impl std::fmt::Display for Error {
@@ -149,6 +195,20 @@ impl std::fmt::Display for Error {
                 "SEV firmware has run out of required resources to carry out command"
             }
             Error::SecureDataInvalid => "SEV platform observed a failed integrity check",
+            Error::InvalidPageSize => "The RMP page size is incorrect",
+            Error::InvalidPageState => "The RMP page state is incorrect",
+            Error::InvalidMdataEntry => "The metadata entry is invalid",
+            Error::InvalidPageOwner => "The page ownership is incorrect",
+            Error::AeadOverflow => "The AEAD algorithm would have overflowed",
+            Error::RingBufferExit => "A Mailbox mode command was sent in Ring Buffer mode",
+            Error::RmpInitRequired => "The RMP must be reinitialized",
+            Error::BadSvn => "SVN of provided image is lower than the committed SVN",
+            Error::BadVersion => "Firmware version is not compatible with this command",
+            Error::ShutdownRequired => "Firmware must be shut down before this command",
+            Error::UpdateFailed => "Firmware update failed",
+            Error::RestoreRequired => "Firmware state must be restored after a failed update",
+            Error::RmpInitFailed => "The RMP initialization failed",
+            Error::InvalidKey => "The key requested is invalid, unavailable or of the wrong type",
         };
         write!(f, "{}", err_description)
     }
@@ -216,6 +276,20 @@ impl From<u32> for Indeterminate<Error> {
             22 => Error::InvalidParam,
             23 => Error::ResourceLimit,
             24 => Error::SecureDataInvalid,
+            25 => Error::InvalidPageSize,
+            26 => Error::InvalidPageState,
+            27 => Error::InvalidMdataEntry,
+            28 => Error::InvalidPageOwner,
+            29 => Error::AeadOverflow,
+            31 => Error::RingBufferExit,
+            32 => Error::RmpInitRequired,
+            33 => Error::BadSvn,
+            34 => Error::BadVersion,
+            35 => Error::ShutdownRequired,
+            36 => Error::UpdateFailed,
+            37 => Error::RestoreRequired,
+            38 => Error::RmpInitFailed,
+            39 => Error::InvalidKey,
             _ => return Indeterminate::Unknown,
         })
     }
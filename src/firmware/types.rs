@@ -3,7 +3,11 @@
 use crate::certs::sev;
 use crate::Version;
 
+use serde::{Deserialize, Serialize};
+
+use std::convert::TryFrom;
 use std::marker::PhantomData;
+use std::mem::size_of;
 
 /// Reset the platform's persistent state.
 ///
@@ -155,7 +159,7 @@ impl<'a> GetId<'a> {
 /// TcbVersion represents the version of the firmware.
 ///
 /// (Chapter 2.2; Table 3)
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 #[repr(C)]
 pub struct TcbVersion {
     /// Current bootloader version.
@@ -202,3 +206,99 @@ pub struct SnpPlatformStatus {
     /// Reported TCB version.
     pub reported_tcb_version: TcbVersion,
 }
+
+/// Commit the currently installed firmware, raising the committed TCB to
+/// the current TCB.
+///
+/// (Chapter 8.4)
+pub struct SnpCommit;
+
+bitflags::bitflags! {
+    /// Options of the SEV-SNP platform configuration.
+    #[derive(Default, Deserialize, Serialize)]
+    pub struct SnpConfigMask: u32 {
+        /// If set, the chip ID is masked (zeroed) in attestation reports.
+        const MASK_CHIP_ID  = 1 << 0;
+
+        /// If set, the VCEK is not used to sign attestation reports.
+        const MASK_CHIP_KEY = 1 << 1;
+    }
+}
+
+/// Set the system-wide configuration of the SEV-SNP platform.
+///
+/// (Chapter 8.5; Table 45)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct SnpConfig {
+    /// The TCB version reported in guest attestation reports (and used to
+    /// derive the VCEK). It may not be higher than the current TCB.
+    pub reported_tcb: TcbVersion,
+
+    /// The configuration options.
+    pub mask: SnpConfigMask,
+
+    _reserved: [u8; 52],
+}
+
+impl SnpConfig {
+    /// Create a configuration with the given reported TCB and options.
+    pub fn new(reported_tcb: TcbVersion, mask: SnpConfigMask) -> Self {
+        Self {
+            reported_tcb,
+            mask,
+            _reserved: [0; 52],
+        }
+    }
+}
+
+impl Default for SnpConfig {
+    fn default() -> Self {
+        Self::new(TcbVersion::default(), SnpConfigMask::default())
+    }
+}
+
+/// A VLEK hashstick wrapped by the AMD Key Distribution Service for the
+/// platform it is loaded on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct WrappedVlekHashstick {
+    /// The wrapped hashstick.
+    pub data: [u8; 432],
+}
+
+impl TryFrom<&[u8]> for WrappedVlekHashstick {
+    type Error = std::io::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != size_of::<Self>() {
+            return Err(std::io::ErrorKind::InvalidInput.into());
+        }
+
+        let mut data = [0u8; 432];
+        data.copy_from_slice(value);
+        Ok(Self { data })
+    }
+}
+
+/// Load a wrapped VLEK hashstick into the platform.
+#[repr(C)]
+pub struct SnpVlekLoad<'a> {
+    len: u32,
+    version: u8,
+    _reserved: [u8; 3],
+    address: u64,
+    _phantom: PhantomData<&'a WrappedVlekHashstick>,
+}
+
+impl<'a> SnpVlekLoad<'a> {
+    pub fn new(hashstick: &'a WrappedVlekHashstick) -> Self {
+        Self {
+            len: size_of::<Self>() as _,
+            version: 0,
+            _reserved: [0; 3],
+            address: hashstick as *const _ as _,
+            _phantom: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,368 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The CPUID page of an SEV-SNP guest, through which the hypervisor
+//! provides the guest with CPUID function values that the firmware has
+//! checked against the capabilities of the platform.
+//!
+//! The page is measured into the guest with [`PageType::Cpuid`]. If the
+//! firmware rejects any entry, `Launcher::update_data` fails and the
+//! firmware writes the page back with the offending values corrected.
+//! Decode the returned page and [`CpuidPage::diff`] it against the page
+//! that was submitted to learn which values were rejected.
+//!
+//! [`PageType::Cpuid`]: super::snp::PageType::Cpuid
+
+use crate::util::*;
+
+use std::io::{ErrorKind, Read, Result, Write};
+
+use serde::{Deserialize, Serialize};
+
+/// The maximum number of entries of a CPUID page.
+pub const MAX_ENTRIES: usize = 64;
+
+/// The size of an XSAVE area holding only the x87 state (the legacy
+/// region and the XSAVE header).
+const XSAVE_BASE_SIZE: u32 = 0x240;
+
+/// The values of the four registers returned by a CPUID function.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Registers {
+    /// The value of EAX.
+    pub eax: u32,
+
+    /// The value of EBX.
+    pub ebx: u32,
+
+    /// The value of ECX.
+    pub ecx: u32,
+
+    /// The value of EDX.
+    pub edx: u32,
+}
+
+/// A source of CPUID function values.
+pub trait CpuidSource {
+    /// The values returned by the CPUID function `leaf` (EAX) for the
+    /// given `subleaf` (ECX).
+    fn cpuid(&mut self, leaf: u32, subleaf: u32) -> Registers;
+}
+
+impl<F: FnMut(u32, u32) -> Registers> CpuidSource for F {
+    fn cpuid(&mut self, leaf: u32, subleaf: u32) -> Registers {
+        self(leaf, subleaf)
+    }
+}
+
+/// The CPUID instruction of the host.
+#[cfg(target_arch = "x86_64")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Host;
+
+#[cfg(target_arch = "x86_64")]
+impl CpuidSource for Host {
+    #[allow(unused_unsafe)]
+    fn cpuid(&mut self, leaf: u32, subleaf: u32) -> Registers {
+        let r = unsafe { std::arch::x86_64::__cpuid_count(leaf, subleaf) };
+
+        Registers {
+            eax: r.eax,
+            ebx: r.ebx,
+            ecx: r.ecx,
+            edx: r.edx,
+        }
+    }
+}
+
+/// A register of a CPUID function.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Register {
+    /// EAX.
+    Eax,
+
+    /// EBX.
+    Ebx,
+
+    /// ECX.
+    Ecx,
+
+    /// EDX.
+    Edx,
+}
+
+impl std::fmt::Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Register::Eax => "EAX",
+            Register::Ebx => "EBX",
+            Register::Ecx => "ECX",
+            Register::Edx => "EDX",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// An entry of the CPUID page.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CpuidEntry {
+    /// The CPUID function (EAX).
+    pub eax_in: u32,
+
+    /// The CPUID subfunction (ECX).
+    pub ecx_in: u32,
+
+    /// The value of XCR0 the entry applies to (for function 0xD).
+    pub xcr0_in: u64,
+
+    /// The value of IA32_XSS the entry applies to (for function 0xD).
+    pub xss_in: u64,
+
+    /// The value of EAX returned by the function.
+    pub eax: u32,
+
+    /// The value of EBX returned by the function.
+    pub ebx: u32,
+
+    /// The value of ECX returned by the function.
+    pub ecx: u32,
+
+    /// The value of EDX returned by the function.
+    pub edx: u32,
+
+    _reserved: u64,
+}
+
+impl CpuidEntry {
+    /// Create an entry for the given function and subfunction.
+    pub fn new(leaf: u32, subleaf: u32, registers: Registers) -> Self {
+        Self {
+            eax_in: leaf,
+            ecx_in: subleaf,
+            eax: registers.eax,
+            ebx: registers.ebx,
+            ecx: registers.ecx,
+            edx: registers.edx,
+            ..Default::default()
+        }
+    }
+
+    /// The values of the registers returned by the function.
+    pub fn registers(&self) -> Registers {
+        Registers {
+            eax: self.eax,
+            ebx: self.ebx,
+            ecx: self.ecx,
+            edx: self.edx,
+        }
+    }
+}
+
+/// A value of a CPUID page entry that the firmware rejected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The CPUID function of the entry.
+    pub leaf: u32,
+
+    /// The CPUID subfunction of the entry.
+    pub subleaf: u32,
+
+    /// The rejected register.
+    pub register: Register,
+
+    /// The value that was submitted.
+    pub submitted: u32,
+
+    /// The value the firmware wrote back.
+    pub corrected: u32,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CPUID {:#010x}.{:#x} {}: submitted {:#010x}, firmware returned {:#010x}",
+            self.leaf, self.subleaf, self.register, self.submitted, self.corrected
+        )
+    }
+}
+
+/// The CPUID page of an SEV-SNP guest.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CpuidPage {
+    count: u32,
+    _reserved_0: u32,
+    _reserved_1: u64,
+    entries: [CpuidEntry; MAX_ENTRIES],
+    _reserved_2: [u8; 1008],
+}
+
+impl Default for CpuidPage {
+    fn default() -> Self {
+        // All fields are integers (or arrays of them) for which zero is valid.
+        unsafe { std::mem::zeroed() }
+    }
+}
+
+impl CpuidPage {
+    /// Create an empty CPUID page.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a CPUID page with the functions of the host.
+    #[cfg(target_arch = "x86_64")]
+    pub fn host() -> Result<Self> {
+        Self::from_source(&mut Host)
+    }
+
+    /// Create a CPUID page with the functions of the given source.
+    ///
+    /// All basic and extended functions are enumerated, along with the
+    /// subfunctions of the functions that have them. Functions returning
+    /// all zeroes are left out, as the guest treats missing functions
+    /// within the enumerated range as such.
+    ///
+    /// The XSAVE area sizes of function 0xD are given for XCR0 = 1: the
+    /// guest calculates the sizes for its own XCR0 from the subfunctions.
+    ///
+    /// Fails if the functions do not fit in [`MAX_ENTRIES`] entries.
+    pub fn from_source(source: &mut impl CpuidSource) -> Result<Self> {
+        let mut page = Self::new();
+
+        for base in [0u32, 0x8000_0000].iter().cloned() {
+            let max = source.cpuid(base, 0).eax;
+            if max < base {
+                continue;
+            }
+
+            for leaf in base..=max.min(base + 0xff) {
+                for subleaf in subleaves(source, leaf) {
+                    let mut entry = CpuidEntry::new(leaf, subleaf, source.cpuid(leaf, subleaf));
+
+                    if leaf == 0xd && subleaf <= 1 {
+                        entry.xcr0_in = 1;
+                        entry.ebx = XSAVE_BASE_SIZE;
+                    }
+
+                    if leaf == base || entry.registers() != Registers::default() {
+                        page.push(entry)?;
+                    }
+                }
+            }
+        }
+
+        Ok(page)
+    }
+
+    /// Append an entry.
+    ///
+    /// Fails if the page already holds [`MAX_ENTRIES`] entries.
+    pub fn push(&mut self, entry: CpuidEntry) -> Result<()> {
+        let slot = self
+            .entries
+            .get_mut(self.count as usize)
+            .ok_or(ErrorKind::InvalidInput)?;
+
+        *slot = entry;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// The entries of the page.
+    pub fn entries(&self) -> &[CpuidEntry] {
+        &self.entries[..(self.count as usize).min(MAX_ENTRIES)]
+    }
+
+    /// The entry for the given function and subfunction, if any.
+    pub fn get(&self, leaf: u32, subleaf: u32) -> Option<&CpuidEntry> {
+        self.entries()
+            .iter()
+            .find(|e| e.eax_in == leaf && e.ecx_in == subleaf)
+    }
+
+    /// Compare this (submitted) page with the page the firmware returned
+    /// after rejecting it, listing every register value it corrected.
+    pub fn diff(&self, returned: &CpuidPage) -> Vec<Mismatch> {
+        let mut mismatches = vec![];
+
+        for (submitted, corrected) in self.entries().iter().zip(returned.entries()) {
+            let registers = [
+                (Register::Eax, submitted.eax, corrected.eax),
+                (Register::Ebx, submitted.ebx, corrected.ebx),
+                (Register::Ecx, submitted.ecx, corrected.ecx),
+                (Register::Edx, submitted.edx, corrected.edx),
+            ];
+
+            for (register, s, c) in registers.iter().cloned() {
+                if s != c {
+                    mismatches.push(Mismatch {
+                        leaf: submitted.eax_in,
+                        subleaf: submitted.ecx_in,
+                        register,
+                        submitted: s,
+                        corrected: c,
+                    });
+                }
+            }
+        }
+
+        mismatches
+    }
+}
+
+/// The subfunctions of a CPUID function.
+fn subleaves(source: &mut impl CpuidSource, leaf: u32) -> Vec<u32> {
+    match leaf {
+        // Cache properties, until the null cache type.
+        0x4 | 0x8000_001d => (0..64)
+            .take_while(|i| source.cpuid(leaf, *i).eax & 0x1f != 0)
+            .collect(),
+
+        // Topology levels, until the invalid level type.
+        0xb | 0x1f | 0x8000_0026 => (0..64)
+            .take_while(|i| *i == 0 || (source.cpuid(leaf, *i).ecx >> 8) & 0xff != 0)
+            .collect(),
+
+        // The XSAVE state components supported in XCR0 or IA32_XSS.
+        0xd => {
+            let base = source.cpuid(0xd, 0);
+            let ext = source.cpuid(0xd, 1);
+            let mask = u64::from(base.eax | ext.ecx) | u64::from(base.edx | ext.edx) << 32;
+            (0..64).filter(|i| *i <= 1 || mask & 1 << i != 0).collect()
+        }
+
+        // The maximum subfunction is reported by subfunction 0.
+        0x7 | 0x14 | 0x17 | 0x18 | 0x1d | 0x20 | 0x23 => {
+            (0..=source.cpuid(leaf, 0).eax.min(63)).collect()
+        }
+
+        // Resource monitoring and allocation, and SGX.
+        0xf | 0x10 | 0x12 | 0x8000_0020 => (0..4).collect(),
+
+        _ => vec![0],
+    }
+}
+
+impl codicon::Decoder<()> for CpuidPage {
+    type Error = std::io::Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> Result<Self> {
+        let page: Self = reader.load()?;
+        if page.count as usize > MAX_ENTRIES {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        Ok(page)
+    }
+}
+
+impl codicon::Encoder<()> for CpuidPage {
+    type Error = std::io::Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> Result<()> {
+        writer.save(self)
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The table of kernel, initrd and command line hashes used for measured
+//! direct boot.
+//!
+//! When booting a kernel directly, QEMU (with `kernel-hashes=on`) inserts
+//! a table of SHA-256 hashes of the kernel, the initrd and the command line
+//! into the region OVMF reserves for it (see
+//! [`Ovmf::sev_hash_table`](super::ovmf::Ovmf::sev_hash_table)). OVMF
+//! refuses to boot binaries which do not match those hashes, and since the
+//! table is part of the launch measurement, so are the binaries.
+
+/// The GUID of the table (9438d606-4f22-4cc9-b479-a793d411fd21).
+const TABLE_GUID: [u8; 16] = [
+    0x06, 0xd6, 0x38, 0x94, 0x22, 0x4f, 0xc9, 0x4c, 0xb4, 0x79, 0xa7, 0x93, 0xd4, 0x11, 0xfd, 0x21,
+];
+
+/// The GUID of the kernel entry (4de79437-abd2-427f-b835-d5b172d2045b).
+const KERNEL_GUID: [u8; 16] = [
+    0x37, 0x94, 0xe7, 0x4d, 0xd2, 0xab, 0x7f, 0x42, 0xb8, 0x35, 0xd5, 0xb1, 0x72, 0xd2, 0x04, 0x5b,
+];
+
+/// The GUID of the initrd entry (44baf731-3a2f-4bd7-9af1-41e29169781d).
+const INITRD_GUID: [u8; 16] = [
+    0x31, 0xf7, 0xba, 0x44, 0x2f, 0x3a, 0xd7, 0x4b, 0x9a, 0xf1, 0x41, 0xe2, 0x91, 0x69, 0x78, 0x1d,
+];
+
+/// The GUID of the command line entry (97d02dd8-bd20-4c94-aa78-e7714d36ab2a).
+const CMDLINE_GUID: [u8; 16] = [
+    0xd8, 0x2d, 0xd0, 0x97, 0x20, 0xbd, 0x94, 0x4c, 0xaa, 0x78, 0xe7, 0x71, 0x4d, 0x36, 0xab, 0x2a,
+];
+
+/// The size of an entry: a GUID, a 16-bit length and a SHA-256 hash.
+const ENTRY_LEN: usize = 16 + 2 + 32;
+
+/// The size of the table: a GUID, a 16-bit length and three entries.
+const TABLE_LEN: usize = 16 + 2 + 3 * ENTRY_LEN;
+
+/// The size of a guest page.
+const PAGE_SIZE: usize = 4096;
+
+/// The table of hashes verified by OVMF before booting a kernel directly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HashTable([u8; HashTable::LEN]);
+
+impl HashTable {
+    /// The size of the table, padded to a multiple of 16 bytes. This is
+    /// the number of bytes encrypted (and measured) by the hypervisor.
+    pub const LEN: usize = (TABLE_LEN + 15) & !15;
+
+    /// Calculate the table for the given kernel, initrd and command line.
+    ///
+    /// As QEMU does, the command line is hashed including its terminating
+    /// NUL byte. An empty initrd should be passed if none is used.
+    #[cfg(feature = "openssl")]
+    pub fn new(kernel: &[u8], initrd: &[u8], cmdline: &str) -> Self {
+        use openssl::sha::{sha256, Sha256};
+
+        let mut sha = Sha256::new();
+        sha.update(cmdline.as_bytes());
+        sha.update(&[0]);
+
+        Self::from_digests(sha256(kernel), sha256(initrd), sha.finish())
+    }
+
+    /// Build the table from the SHA-256 digests of the kernel, initrd and
+    /// command line.
+    pub fn from_digests(kernel: [u8; 32], initrd: [u8; 32], cmdline: [u8; 32]) -> Self {
+        let mut table = [0u8; HashTable::LEN];
+        table[..16].copy_from_slice(&TABLE_GUID);
+        table[16..18].copy_from_slice(&(TABLE_LEN as u16).to_le_bytes());
+
+        let entries = [
+            (CMDLINE_GUID, cmdline),
+            (INITRD_GUID, initrd),
+            (KERNEL_GUID, kernel),
+        ];
+
+        for (i, (guid, digest)) in entries.iter().enumerate() {
+            let entry = &mut table[18 + i * ENTRY_LEN..][..ENTRY_LEN];
+            entry[..16].copy_from_slice(guid);
+            entry[16..18].copy_from_slice(&(ENTRY_LEN as u16).to_le_bytes());
+            entry[18..].copy_from_slice(digest);
+        }
+
+        Self(table)
+    }
+
+    /// The padded table, as passed to `launch::sev::Launcher::update_data`
+    /// (and `launch::sev::LaunchDigest::update_data`) for SEV and SEV-ES
+    /// guests.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The page holding the table at the given offset, as inserted into
+    /// SEV-SNP guests as a normal page (see
+    /// [`Section::page_type`](super::ovmf::Section::page_type)).
+    pub fn page(&self, offset: usize) -> Option<Vec<u8>> {
+        if offset.checked_add(HashTable::LEN)? > PAGE_SIZE {
+            return None;
+        }
+
+        let mut page = vec![0u8; PAGE_SIZE];
+        page[offset..][..HashTable::LEN].copy_from_slice(&self.0);
+        Some(page)
+    }
+}
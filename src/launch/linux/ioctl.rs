@@ -19,11 +19,26 @@ impl_const_id! {
     pub Id => u32;
 
     sev::Init = 0,
+    sev::EsInit = 1,
     sev::LaunchStart<'_> = 2,
     sev::LaunchUpdateData<'_> = 3,
+    sev::LaunchUpdateVmsa = 4,
     sev::LaunchSecret<'_> = 5,
     sev::LaunchMeasure<'_> = 6,
     sev::LaunchFinish = 7,
+    sev::SendStart<'_> = 8,
+    sev::SendUpdateData<'_> = 9,
+    sev::SendUpdateVmsa<'_> = 10,
+    sev::SendFinish = 11,
+    sev::ReceiveStart<'_> = 12,
+    sev::ReceiveUpdateData<'_> = 13,
+    sev::ReceiveUpdateVmsa<'_> = 14,
+    sev::ReceiveFinish = 15,
+    sev::GetGuestStatus = 16,
+    sev::DbgDecrypt<'_> = 17,
+    sev::DbgEncrypt<'_> = 18,
+    sev::GetAttestationReport<'_> = 20,
+    sev::SendCancel = 21,
 
     snp::Init = 22,
     snp::LaunchStart<'_> = 23,
@@ -49,6 +64,9 @@ const ENC_OP: Ioctl<WriteRead, &c_ulong> = unsafe { KVM.write_read(0xBA) };
 /// Initialize the SEV platform context.
 pub const INIT: Ioctl<WriteRead, &Command<sev::Init>> = unsafe { ENC_OP.lie() };
 
+/// Initialize the SEV-ES platform context.
+pub const ES_INIT: Ioctl<WriteRead, &Command<sev::EsInit>> = unsafe { ENC_OP.lie() };
+
 /// Create encrypted guest context.
 pub const LAUNCH_START: Ioctl<WriteRead, &Command<sev::LaunchStart>> = unsafe { ENC_OP.lie() };
 
@@ -56,6 +74,10 @@ pub const LAUNCH_START: Ioctl<WriteRead, &Command<sev::LaunchStart>> = unsafe {
 pub const LAUNCH_UPDATE_DATA: Ioctl<WriteRead, &Command<sev::LaunchUpdateData>> =
     unsafe { ENC_OP.lie() };
 
+/// Encrypt the VMSAs of all vCPUs with the guest's VEK.
+pub const LAUNCH_UPDATE_VMSA: Ioctl<WriteRead, &Command<sev::LaunchUpdateVmsa>> =
+    unsafe { ENC_OP.lie() };
+
 /// Inject a secret into the guest.
 pub const LAUNCH_SECRET: Ioctl<WriteRead, &Command<sev::LaunchSecret>> = unsafe { ENC_OP.lie() };
 
@@ -67,10 +89,58 @@ pub const LAUNCH_MEASUREMENT: Ioctl<WriteRead, &Command<sev::LaunchMeasure>> =
 /// the ready state.
 pub const LAUNCH_FINISH: Ioctl<WriteRead, &Command<sev::LaunchFinish>> = unsafe { ENC_OP.lie() };
 
+/// Query the status of a guest.
+pub const GUEST_STATUS: Ioctl<WriteRead, &Command<sev::GetGuestStatus>> = unsafe { ENC_OP.lie() };
+
+/// Decrypt guest memory for debugging.
+pub const DBG_DECRYPT: Ioctl<WriteRead, &Command<sev::DbgDecrypt>> = unsafe { ENC_OP.lie() };
+
+/// Encrypt data into guest memory for debugging.
+pub const DBG_ENCRYPT: Ioctl<WriteRead, &Command<sev::DbgEncrypt>> = unsafe { ENC_OP.lie() };
+
+/// Get the attestation report of a guest.
+pub const GET_ATTESTATION_REPORT: Ioctl<WriteRead, &Command<sev::GetAttestationReport>> =
+    unsafe { ENC_OP.lie() };
+
+/// Create an outgoing guest context.
+pub const SEND_START: Ioctl<WriteRead, &Command<sev::SendStart>> = unsafe { ENC_OP.lie() };
+
+/// Encrypt a guest page for sending.
+pub const SEND_UPDATE_DATA: Ioctl<WriteRead, &Command<sev::SendUpdateData>> =
+    unsafe { ENC_OP.lie() };
+
+/// Encrypt the VMSA of a vCPU for sending.
+pub const SEND_UPDATE_VMSA: Ioctl<WriteRead, &Command<sev::SendUpdateVmsa>> =
+    unsafe { ENC_OP.lie() };
+
+/// Complete the sending of a guest.
+pub const SEND_FINISH: Ioctl<WriteRead, &Command<sev::SendFinish>> = unsafe { ENC_OP.lie() };
+
+/// Abort the sending of a guest.
+pub const SEND_CANCEL: Ioctl<WriteRead, &Command<sev::SendCancel>> = unsafe { ENC_OP.lie() };
+
+/// Create an incoming guest context.
+pub const RECEIVE_START: Ioctl<WriteRead, &Command<sev::ReceiveStart>> = unsafe { ENC_OP.lie() };
+
+/// Import a received guest page.
+pub const RECEIVE_UPDATE_DATA: Ioctl<WriteRead, &Command<sev::ReceiveUpdateData>> =
+    unsafe { ENC_OP.lie() };
+
+/// Import the received VMSA of a vCPU.
+pub const RECEIVE_UPDATE_VMSA: Ioctl<WriteRead, &Command<sev::ReceiveUpdateVmsa>> =
+    unsafe { ENC_OP.lie() };
+
+/// Complete the receiving of a guest.
+pub const RECEIVE_FINISH: Ioctl<WriteRead, &Command<sev::ReceiveFinish>> = unsafe { ENC_OP.lie() };
+
 /// Corresponds to the `KVM_MEMORY_ENCRYPT_REG_REGION` ioctl
 pub const ENC_REG_REGION: Ioctl<Write, &KvmEncRegion> =
     unsafe { KVM.read::<KvmEncRegion>(0xBB).lie() };
 
+/// Corresponds to the `KVM_MEMORY_ENCRYPT_UNREG_REGION` ioctl
+pub const ENC_UNREG_REGION: Ioctl<Write, &KvmEncRegion> =
+    unsafe { KVM.read::<KvmEncRegion>(0xBC).lie() };
+
 /// Initialize the SEV-SNP platform in KVM.
 pub const SNP_INIT: Ioctl<WriteRead, &Command<snp::Init>> = unsafe { ENC_OP.lie() };
 
@@ -104,10 +174,28 @@ impl<'a> KvmEncRegion<'a> {
         }
     }
 
+    /// Create a new `KvmEncRegion` referencing memory by its address and size, such
+    /// as a region that was registered while it was borrowed.
+    pub fn from_raw(addr: usize, size: usize) -> KvmEncRegion<'static> {
+        KvmEncRegion {
+            addr: addr as _,
+            size: size as _,
+            phantom: PhantomData,
+        }
+    }
+
     /// Register the encrypted memory region to a virtual machine
     pub fn register(&mut self, vm_fd: &mut impl AsRawFd) -> std::io::Result<std::os::raw::c_uint> {
         ENC_REG_REGION.ioctl(vm_fd, self)
     }
+
+    /// Unregister the encrypted memory region from a virtual machine
+    pub fn unregister(
+        &mut self,
+        vm_fd: &mut impl AsRawFd,
+    ) -> std::io::Result<std::os::raw::c_uint> {
+        ENC_UNREG_REGION.ioctl(vm_fd, self)
+    }
 }
 
 /// A generic SEV command
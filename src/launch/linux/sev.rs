@@ -12,8 +12,9 @@ use std::mem::{size_of_val, MaybeUninit};
 #[repr(C)]
 pub struct Init;
 
-#[repr(transparent)]
-pub struct Handle(u32);
+/// Initialize the SEV-ES platform context.
+#[repr(C)]
+pub struct EsInit;
 
 impl From<LaunchStart<'_>> for Handle {
     fn from(ls: LaunchStart) -> Self {
@@ -36,7 +37,7 @@ pub struct LaunchStart<'a> {
 impl<'a> LaunchStart<'a> {
     pub fn new(policy: &'a Policy, dh: &'a Certificate, session: &'a Session) -> Self {
         Self {
-            handle: Handle(0), /* platform will generate one for us */
+            handle: Handle::from(0), /* platform will generate one for us */
             policy: *policy,
             dh_addr: dh as *const _ as _,
             dh_len: size_of_val(dh) as _,
@@ -109,7 +110,276 @@ impl<'a> LaunchMeasure<'a> {
     }
 }
 
+/// Encrypt the VMSAs of all vCPUs of an SEV-ES guest with its VEK.
+#[repr(C)]
+pub struct LaunchUpdateVmsa;
+
 /// Complete the SEV launch flow and transition guest into
 /// ready state.
 #[repr(C)]
 pub struct LaunchFinish;
+
+/// Create an outgoing guest context and wrap its transport keys for the
+/// target platform.
+#[repr(C)]
+pub struct SendStart<'a> {
+    policy: Policy,
+    pdh_addr: u64,
+    pdh_len: u32,
+    plat_certs_addr: u64,
+    plat_certs_len: u32,
+    amd_certs_addr: u64,
+    amd_certs_len: u32,
+    session_addr: u64,
+    session_len: u32,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> SendStart<'a> {
+    pub fn new(
+        pdh: &'a Certificate,
+        plat_certs: &'a [u8],
+        amd_certs: &'a [u8],
+        session: &'a mut MaybeUninit<Session>,
+    ) -> Self {
+        Self {
+            policy: Policy::default(), /* filled in by the platform */
+            pdh_addr: pdh as *const _ as _,
+            pdh_len: size_of_val(pdh) as _,
+            plat_certs_addr: plat_certs.as_ptr() as _,
+            plat_certs_len: plat_certs.len() as _,
+            amd_certs_addr: amd_certs.as_ptr() as _,
+            amd_certs_len: amd_certs.len() as _,
+            session_addr: session.as_mut_ptr() as _,
+            session_len: size_of_val(session) as _,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Encrypt a guest page with the transport keys for sending.
+#[repr(C)]
+pub struct SendUpdateData<'a> {
+    hdr_addr: u64,
+    hdr_len: u32,
+    guest_addr: u64,
+    guest_len: u32,
+    trans_addr: u64,
+    trans_len: u32,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> SendUpdateData<'a> {
+    pub fn new(header: &'a mut MaybeUninit<Header>, guest: &'a [u8], trans: &'a mut [u8]) -> Self {
+        Self {
+            hdr_addr: header.as_mut_ptr() as _,
+            hdr_len: size_of_val(header) as _,
+            guest_addr: guest.as_ptr() as _,
+            guest_len: guest.len() as _,
+            trans_addr: trans.as_mut_ptr() as _,
+            trans_len: trans.len() as _,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Encrypt the VMSA of a vCPU with the transport keys for sending.
+#[repr(C)]
+pub struct SendUpdateVmsa<'a> {
+    vcpu_id: u32,
+    hdr_addr: u64,
+    hdr_len: u32,
+    trans_addr: u64,
+    trans_len: u32,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> SendUpdateVmsa<'a> {
+    pub fn new(vcpu_id: u32, header: &'a mut MaybeUninit<Header>, trans: &'a mut [u8]) -> Self {
+        Self {
+            vcpu_id,
+            hdr_addr: header.as_mut_ptr() as _,
+            hdr_len: size_of_val(header) as _,
+            trans_addr: trans.as_mut_ptr() as _,
+            trans_len: trans.len() as _,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Complete the sending of a guest.
+#[repr(C)]
+pub struct SendFinish;
+
+/// Abort the sending of a guest.
+#[repr(C)]
+pub struct SendCancel;
+
+/// Create an incoming guest context.
+#[repr(C)]
+pub struct ReceiveStart<'a> {
+    handle: Handle,
+    policy: Policy,
+    pdh_addr: u64,
+    pdh_len: u32,
+    session_addr: u64,
+    session_len: u32,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> ReceiveStart<'a> {
+    pub fn new(policy: &'a Policy, pdh: &'a Certificate, session: &'a Session) -> Self {
+        Self {
+            handle: Handle::from(0), /* platform will generate one for us */
+            policy: *policy,
+            pdh_addr: pdh as *const _ as _,
+            pdh_len: size_of_val(pdh) as _,
+            session_addr: session as *const _ as _,
+            session_len: size_of_val(session) as _,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl From<ReceiveStart<'_>> for Handle {
+    fn from(rs: ReceiveStart) -> Self {
+        rs.handle
+    }
+}
+
+/// Decrypt a received guest page and re-encrypt it with the guest's VEK.
+#[repr(C)]
+pub struct ReceiveUpdateData<'a> {
+    hdr_addr: u64,
+    hdr_len: u32,
+    guest_addr: u64,
+    guest_len: u32,
+    trans_addr: u64,
+    trans_len: u32,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> ReceiveUpdateData<'a> {
+    pub fn new(header: &'a Header, guest: &'a mut [u8], trans: &'a [u8]) -> Self {
+        Self {
+            hdr_addr: header as *const _ as _,
+            hdr_len: size_of_val(header) as _,
+            guest_addr: guest.as_mut_ptr() as _,
+            guest_len: guest.len() as _,
+            trans_addr: trans.as_ptr() as _,
+            trans_len: trans.len() as _,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Decrypt a received VMSA and re-encrypt it with the guest's VEK.
+#[repr(C)]
+pub struct ReceiveUpdateVmsa<'a> {
+    vcpu_id: u32,
+    hdr_addr: u64,
+    hdr_len: u32,
+    trans_addr: u64,
+    trans_len: u32,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> ReceiveUpdateVmsa<'a> {
+    pub fn new(vcpu_id: u32, header: &'a Header, trans: &'a [u8]) -> Self {
+        Self {
+            vcpu_id,
+            hdr_addr: header as *const _ as _,
+            hdr_len: size_of_val(header) as _,
+            trans_addr: trans.as_ptr() as _,
+            trans_len: trans.len() as _,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Complete the receiving of a guest.
+#[repr(C)]
+pub struct ReceiveFinish;
+
+/// Get the attestation report of a guest, signed by the PEK.
+#[repr(C)]
+pub struct GetAttestationReport<'a> {
+    mnonce: [u8; 16],
+    addr: u64,
+    len: u32,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> GetAttestationReport<'a> {
+    pub fn new(mnonce: [u8; 16], report: &'a mut MaybeUninit<AttestationReport>) -> Self {
+        Self {
+            mnonce,
+            addr: report.as_mut_ptr() as _,
+            len: size_of_val(report) as _,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Decrypt guest memory for debugging.
+#[repr(C)]
+pub struct DbgDecrypt<'a> {
+    src_addr: u64,
+    dst_addr: u64,
+    len: u32,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> DbgDecrypt<'a> {
+    pub fn new(src: usize, dst: &'a mut [u8]) -> Self {
+        Self {
+            src_addr: src as _,
+            dst_addr: dst.as_mut_ptr() as _,
+            len: dst.len() as _,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Encrypt data into guest memory for debugging.
+#[repr(C)]
+pub struct DbgEncrypt<'a> {
+    src_addr: u64,
+    dst_addr: u64,
+    len: u32,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> DbgEncrypt<'a> {
+    pub fn new(src: &'a [u8], dst: usize) -> Self {
+        Self {
+            src_addr: src.as_ptr() as _,
+            dst_addr: dst as _,
+            len: src.len() as _,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Query the status of a guest.
+#[repr(C)]
+#[derive(Default)]
+pub struct GetGuestStatus {
+    handle: u32,
+    policy: u32,
+    state: u32,
+}
+
+impl GetGuestStatus {
+    pub fn handle(&self) -> Handle {
+        Handle::from(self.handle)
+    }
+
+    pub fn policy(&self) -> Policy {
+        Policy::from_bits_truncate(self.policy)
+    }
+
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+}
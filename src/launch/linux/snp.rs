@@ -150,7 +150,11 @@ impl From<Finish<'_, '_>> for LaunchFinish<'_> {
             id_block_uaddr: id_block,
             id_auth_uaddr: id_auth,
             id_block_en: if finish.id_block.is_some() { 1 } else { 0 },
-            auth_key_en: if finish.id_auth.is_some() { 1 } else { 0 },
+            auth_key_en: match finish.id_auth {
+                // The author key algorithm is zero if there is no author key.
+                Some(auth) if matches!(auth.get(4..8), Some(a) if a != [0u8; 4]) => 1,
+                _ => 0,
+            },
             host_data: finish.host_data,
             pad: [0u8; 6],
             _phantom: PhantomData,
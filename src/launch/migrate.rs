@@ -0,0 +1,345 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! An implementation of the SEV live migration process as a pair of
+//! type-state machines: a `Sender` on the source platform and a `Receiver`
+//! on the target platform. This ensures (at compile time) that the right
+//! steps are called in the right order.
+//!
+//! The guest memory leaves the source platform encrypted with transport
+//! keys that are wrapped for the target platform's PDH, and is re-encrypted
+//! with the guest's new VEK on the target platform.
+
+#[cfg(target_os = "linux")]
+use crate::launch::linux::ioctl::*;
+#[cfg(target_os = "linux")]
+use crate::launch::linux::sev::*;
+use crate::launch::sev::{Handle, Header, Policy, PolicyFlags, Start};
+use crate::*;
+
+use std::io::{ErrorKind, Result};
+use std::mem::MaybeUninit;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use codicon::Encoder;
+use serde::{Deserialize, Serialize};
+
+/// Sender type-state that indicates a guest which is ready to be sent.
+pub struct Ready(Policy);
+
+/// Sender type-state that indicates an in-progress send.
+pub struct Sending(Start);
+
+/// Facilitates the correct execution of the SEV send process.
+pub struct Sender<'a, T, U: AsRawFd, V: AsRawFd> {
+    state: T,
+    vm_fd: &'a mut U,
+    sev: &'a mut V,
+}
+
+impl<'a, U: AsRawFd, V: AsRawFd> Sender<'a, Ready, U, V> {
+    /// Prepare to send a running guest with the given policy.
+    ///
+    /// Fails if the policy forbids sending the guest (see
+    /// [`PolicyFlags::NO_SEND`]).
+    pub fn new(vm_fd: &'a mut U, sev: &'a mut V, policy: Policy) -> Result<Self> {
+        if policy.flags.contains(PolicyFlags::NO_SEND) {
+            return Err(ErrorKind::PermissionDenied.into());
+        }
+
+        Ok(Sender {
+            state: Ready(policy),
+            vm_fd,
+            sev,
+        })
+    }
+
+    /// Create an outgoing guest context for the target platform.
+    ///
+    /// The transport keys are wrapped for the PDH of the `target` chain.
+    /// The `pdh` of this (the source) platform is passed on to the target
+    /// platform as part of [`Sender::receive_start`].
+    pub fn start(
+        self,
+        target: &certs::Chain,
+        pdh: certs::sev::Certificate,
+    ) -> Result<Sender<'a, Sending, U, V>> {
+        let mut plat_certs = vec![];
+        target.sev.pek.encode(&mut plat_certs, ())?;
+        target.sev.oca.encode(&mut plat_certs, ())?;
+        target.sev.cek.encode(&mut plat_certs, ())?;
+
+        let mut amd_certs = vec![];
+        target.ca.encode(&mut amd_certs, ())?;
+
+        let mut session = MaybeUninit::uninit();
+        let mut send_start = SendStart::new(&target.sev.pdh, &plat_certs, &amd_certs, &mut session);
+        let mut cmd = Command::from_mut(self.sev, &mut send_start);
+        SEND_START
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        let next = Sender {
+            state: Sending(Start {
+                policy: self.state.0,
+                cert: pdh,
+                session: unsafe { session.assume_init() },
+            }),
+            vm_fd: self.vm_fd,
+            sev: self.sev,
+        };
+
+        Ok(next)
+    }
+}
+
+impl<'a, U: AsRawFd, V: AsRawFd> Sender<'a, Sending, U, V> {
+    /// The data the target platform needs to create the incoming guest
+    /// context (see [`Receiver::start`]).
+    pub fn receive_start(&self) -> Start {
+        self.state.0
+    }
+
+    /// Encrypt guest memory with the transport keys.
+    ///
+    /// The length of `data` must be a multiple of 16 bytes.
+    pub fn update_data(&mut self, data: &[u8]) -> Result<Packet> {
+        let mut header = MaybeUninit::uninit();
+        let mut ciphertext = vec![0u8; data.len()];
+        let send_update_data = SendUpdateData::new(&mut header, data, &mut ciphertext);
+        let mut cmd = Command::from(self.sev, &send_update_data);
+        SEND_UPDATE_DATA
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Ok(Packet {
+            header: unsafe { header.assume_init() },
+            ciphertext,
+        })
+    }
+
+    /// Encrypt the VMSA of a vCPU of an SEV-ES guest with the transport
+    /// keys.
+    pub fn update_vmsa(&mut self, vcpu_id: u32) -> Result<Packet> {
+        let mut header = MaybeUninit::uninit();
+        let mut ciphertext = vec![0u8; 4096];
+        let send_update_vmsa = SendUpdateVmsa::new(vcpu_id, &mut header, &mut ciphertext);
+        let mut cmd = Command::from(self.sev, &send_update_vmsa);
+        SEND_UPDATE_VMSA
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Ok(Packet {
+            header: unsafe { header.assume_init() },
+            ciphertext,
+        })
+    }
+
+    /// Complete the SEV send process.
+    pub fn finish(self) -> Result<()> {
+        let mut cmd = Command::from(self.sev, &SendFinish);
+        SEND_FINISH
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+        Ok(())
+    }
+
+    /// Abort the SEV send process, allowing the guest to keep running on
+    /// this platform.
+    pub fn cancel(self) -> Result<()> {
+        let mut cmd = Command::from(self.sev, &SendCancel);
+        SEND_CANCEL
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+        Ok(())
+    }
+}
+
+/// Receiver type-state that indicates a brand new receive.
+pub struct New;
+
+/// Receiver type-state that indicates an in-progress receive.
+pub struct Receiving(Handle, Memory);
+
+/// The guest memory registered with KVM for an in-progress receive.
+///
+/// The memory is unregistered if the receive is dropped before it
+/// completes.
+struct Memory {
+    vm_fd: RawFd,
+    addr: usize,
+    size: usize,
+}
+
+impl Memory {
+    /// Whether `guest` lies within the registered memory.
+    fn contains(&self, guest: &[u8]) -> bool {
+        match (guest.as_ptr() as usize).checked_sub(self.addr) {
+            Some(offset) => offset
+                .checked_add(guest.len())
+                .map(|end| end <= self.size)
+                .unwrap_or(false),
+            None => false,
+        }
+    }
+}
+
+impl Drop for Memory {
+    fn drop(&mut self) {
+        let _ = KvmEncRegion::from_raw(self.addr, self.size).unregister(&mut self.vm_fd);
+    }
+}
+
+/// Facilitates the correct execution of the SEV receive process.
+pub struct Receiver<'a, T, U: AsRawFd, V: AsRawFd> {
+    state: T,
+    vm_fd: &'a mut U,
+    sev: &'a mut V,
+}
+
+impl<'a, T, U: AsRawFd, V: AsRawFd> Receiver<'a, T, U, V> {
+    /// Give access to the vm fd to create vCPUs or such.
+    pub fn as_mut_vmfd(&mut self) -> &mut U {
+        self.vm_fd
+    }
+}
+
+impl<'a, U: AsRawFd, V: AsRawFd> Receiver<'a, New, U, V> {
+    /// Begin the SEV receive process.
+    pub fn new(kvm: &'a mut U, sev: &'a mut V) -> Result<Self> {
+        let receiver = Receiver {
+            vm_fd: kvm,
+            sev,
+            state: New,
+        };
+
+        let mut cmd = Command::from(receiver.sev, &Init);
+        INIT.ioctl(receiver.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Ok(receiver)
+    }
+
+    /// Begin the SEV-ES receive process.
+    pub fn new_es(kvm: &'a mut U, sev: &'a mut V) -> Result<Self> {
+        let receiver = Receiver {
+            vm_fd: kvm,
+            sev,
+            state: New,
+        };
+
+        let mut cmd = Command::from(receiver.sev, &EsInit);
+        ES_INIT
+            .ioctl(receiver.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Ok(receiver)
+    }
+
+    /// Create an incoming guest context from the data produced by the
+    /// source platform (see [`Sender::receive_start`]).
+    ///
+    /// `memory` is the guest memory the packets are received into. It is
+    /// registered with KVM once, for the whole receive.
+    pub fn start(self, start: Start, memory: &[u8]) -> Result<Receiver<'a, Receiving, U, V>> {
+        KvmEncRegion::new(memory).register(self.vm_fd)?;
+        let memory = Memory {
+            vm_fd: self.vm_fd.as_raw_fd(),
+            addr: memory.as_ptr() as usize,
+            size: memory.len(),
+        };
+
+        let mut receive_start = ReceiveStart::new(&start.policy, &start.cert, &start.session);
+        let mut cmd = Command::from_mut(self.sev, &mut receive_start);
+        RECEIVE_START
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        let next = Receiver {
+            state: Receiving(receive_start.into(), memory),
+            vm_fd: self.vm_fd,
+            sev: self.sev,
+        };
+
+        Ok(next)
+    }
+}
+
+impl<'a, U: AsRawFd, V: AsRawFd> Receiver<'a, Receiving, U, V> {
+    /// Decrypt a packet and re-encrypt it into guest memory with the
+    /// guest's VEK.
+    ///
+    /// `guest` must be as long as the ciphertext of the packet and lie
+    /// within the memory passed to [`Receiver::start`].
+    pub fn update_data(&mut self, packet: &Packet, guest: &mut [u8]) -> Result<()> {
+        if guest.len() != packet.ciphertext.len() || !self.state.1.contains(guest) {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+
+        let receive_update_data = ReceiveUpdateData::new(&packet.header, guest, &packet.ciphertext);
+        let mut cmd = Command::from(self.sev, &receive_update_data);
+        RECEIVE_UPDATE_DATA
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Ok(())
+    }
+
+    /// Decrypt a packet and re-encrypt it as the VMSA of a vCPU of an
+    /// SEV-ES guest with the guest's VEK.
+    pub fn update_vmsa(&mut self, vcpu_id: u32, packet: &Packet) -> Result<()> {
+        let receive_update_vmsa =
+            ReceiveUpdateVmsa::new(vcpu_id, &packet.header, &packet.ciphertext);
+        let mut cmd = Command::from(self.sev, &receive_update_vmsa);
+        RECEIVE_UPDATE_VMSA
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Ok(())
+    }
+
+    /// Complete the SEV receive process.
+    ///
+    /// The guest memory stays registered with KVM, as the received guest
+    /// runs from it, until the VM is destroyed.
+    pub fn finish(self) -> Result<Handle> {
+        let mut cmd = Command::from(self.sev, &ReceiveFinish);
+        RECEIVE_FINISH
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        let Receiving(handle, memory) = self.state;
+        std::mem::forget(memory);
+        Ok(handle)
+    }
+}
+
+/// A packet of guest memory (or of a VMSA) encrypted with the transport
+/// keys.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Packet {
+    /// The header for this packet.
+    pub header: Header,
+
+    /// The encrypted guest memory.
+    pub ciphertext: Vec<u8>,
+}
+
+impl codicon::Decoder<()> for Packet {
+    type Error = std::io::Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> std::io::Result<Self> {
+        let header = reader.load()?;
+        let mut ciphertext = vec![];
+        let _ = reader.read_to_end(&mut ciphertext)?;
+        Ok(Self { header, ciphertext })
+    }
+}
+
+impl codicon::Encoder<()> for Packet {
+    type Error = std::io::Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> std::io::Result<()> {
+        writer.save(&self.header)?;
+        writer.write_all(&self.ciphertext)
+    }
+}
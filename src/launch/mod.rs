@@ -9,5 +9,11 @@
 #[cfg(target_os = "linux")]
 mod linux;
 
+pub mod cpuid;
+pub mod hashes;
+#[cfg(target_os = "linux")]
+pub mod migrate;
+pub mod ovmf;
 pub mod sev;
 pub mod snp;
+pub mod vmsa;
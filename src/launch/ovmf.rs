@@ -0,0 +1,364 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Parsing of the metadata OVMF embeds for SEV, SEV-ES and SEV-SNP launches.
+//!
+//! OVMF places a table of GUID-tagged entries right before its reset
+//! vector. Those entries describe the SEV-ES AP reset block, the location
+//! of the SEV kernel hash table and launch secret, and the SEV-SNP metadata
+//! sections which must be populated (and measured) before the guest runs.
+
+use super::hashes::HashTable;
+use super::snp::{PageType, Update, VmplPerms};
+
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Result};
+
+/// The GUID of the OVMF table footer (96b582de-1fb2-45f7-baea-a366c55a082d).
+const TABLE_FOOTER_GUID: [u8; 16] = [
+    0xde, 0x82, 0xb5, 0x96, 0xb2, 0x1f, 0xf7, 0x45, 0xba, 0xea, 0xa3, 0x66, 0xc5, 0x5a, 0x08, 0x2d,
+];
+
+/// The GUID of the SEV hash table entry (7255371f-3a3b-4b04-927b-1da6efa8d454).
+const SEV_HASH_TABLE_GUID: [u8; 16] = [
+    0x1f, 0x37, 0x55, 0x72, 0x3b, 0x3a, 0x04, 0x4b, 0x92, 0x7b, 0x1d, 0xa6, 0xef, 0xa8, 0xd4, 0x54,
+];
+
+/// The GUID of the SEV-ES reset block entry (00f771de-1a7e-4fcb-890e-68c77e2fb44e).
+const SEV_ES_RESET_BLOCK_GUID: [u8; 16] = [
+    0xde, 0x71, 0xf7, 0x00, 0x7e, 0x1a, 0xcb, 0x4f, 0x89, 0x0e, 0x68, 0xc7, 0x7e, 0x2f, 0xb4, 0x4e,
+];
+
+/// The GUID of the SEV launch secret entry (4c2eb361-7d9b-4cc3-8081-127c90d3d294).
+const SEV_SECRET_GUID: [u8; 16] = [
+    0x61, 0xb3, 0x2e, 0x4c, 0x9b, 0x7d, 0xc3, 0x4c, 0x80, 0x81, 0x12, 0x7c, 0x90, 0xd3, 0xd2, 0x94,
+];
+
+/// The GUID of the SEV-SNP metadata entry (dc886566-984a-4798-a75e-5585a7bf67cc).
+const SNP_METADATA_GUID: [u8; 16] = [
+    0x66, 0x65, 0x88, 0xdc, 0x4a, 0x98, 0x98, 0x47, 0xa7, 0x5e, 0x55, 0x85, 0xa7, 0xbf, 0x67, 0xcc,
+];
+
+/// The signature of the SEV-SNP metadata header.
+const SNP_METADATA_SIGNATURE: &[u8; 4] = b"ASEV";
+
+/// The size of a GUID followed by the 16-bit length of a table entry.
+const ENTRY_HEADER_LEN: usize = 18;
+
+/// The number of bytes between the end of the table and the end of the image.
+const TABLE_END_OFFSET: usize = 32;
+
+/// The size of a guest page.
+const PAGE_SIZE: u32 = 4096;
+
+/// The guest physical address at which the end of OVMF is mapped.
+const FOUR_GIB: u64 = 1 << 32;
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32> {
+    match data.get(offset..offset + 4) {
+        Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(ErrorKind::InvalidData.into()),
+    }
+}
+
+/// A region of guest physical memory.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// The guest physical address of the region.
+    pub gpa: u32,
+
+    /// The size of the region in bytes.
+    pub size: u32,
+}
+
+impl TryFrom<&[u8]> for Region {
+    type Error = Error;
+
+    fn try_from(data: &[u8]) -> Result<Self> {
+        Ok(Self {
+            gpa: u32_at(data, 0)?,
+            size: u32_at(data, 4)?,
+        })
+    }
+}
+
+/// The types of sections described by the SEV-SNP metadata.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+#[non_exhaustive]
+pub enum SectionType {
+    /// Memory which must be validated before OVMF runs.
+    SnpSecMemory = 1,
+
+    /// The SEV-SNP secrets page.
+    SnpSecrets = 2,
+
+    /// The SEV-SNP CPUID page.
+    Cpuid = 3,
+
+    /// The calling area of the Secure VM Service Module.
+    SvsmCaa = 4,
+
+    /// The page holding the hashes of the kernel, initrd and command line.
+    SnpKernelHashes = 0x10,
+}
+
+impl TryFrom<u32> for SectionType {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self> {
+        Ok(match value {
+            1 => SectionType::SnpSecMemory,
+            2 => SectionType::SnpSecrets,
+            3 => SectionType::Cpuid,
+            4 => SectionType::SvsmCaa,
+            0x10 => SectionType::SnpKernelHashes,
+            _ => return Err(ErrorKind::InvalidData.into()),
+        })
+    }
+}
+
+/// A section of guest memory described by the SEV-SNP metadata.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    /// The guest memory covered by the section.
+    pub region: Region,
+
+    /// What the section is used for.
+    pub section_type: SectionType,
+}
+
+impl Section {
+    /// The page type with which the section is inserted into the guest.
+    ///
+    /// The kernel hashes page is only measured as a normal page when a
+    /// hash table is inserted; otherwise it is a zero page like the rest
+    /// of the pre-validated memory.
+    pub fn page_type(&self, kernel_hashes: bool) -> PageType {
+        match self.section_type {
+            SectionType::SnpSecrets => PageType::Secrets,
+            SectionType::Cpuid => PageType::Cpuid,
+            SectionType::SnpKernelHashes if kernel_hashes => PageType::Normal,
+            _ => PageType::Zero,
+        }
+    }
+}
+
+/// An OVMF firmware image along with the launch metadata it embeds.
+pub struct Ovmf<'a> {
+    image: &'a [u8],
+    table: Vec<([u8; 16], &'a [u8])>,
+    sections: Vec<Section>,
+}
+
+impl<'a> Ovmf<'a> {
+    /// Parse the launch metadata of an OVMF image.
+    pub fn new(image: &'a [u8]) -> Result<Self> {
+        if image.len() & (PAGE_SIZE as usize - 1) != 0 || image.len() as u64 > FOUR_GIB {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        let mut ovmf = Self {
+            image,
+            table: Self::table(image)?,
+            sections: Vec::new(),
+        };
+
+        if let Some(data) = ovmf.entry(&SNP_METADATA_GUID) {
+            ovmf.sections = Self::metadata(image, u32_at(data, 0)? as usize)?;
+        }
+
+        Ok(ovmf)
+    }
+
+    fn table(image: &'a [u8]) -> Result<Vec<([u8; 16], &'a [u8])>> {
+        let end = image.len().saturating_sub(TABLE_END_OFFSET);
+        if end < ENTRY_HEADER_LEN || image[end - 16..end] != TABLE_FOOTER_GUID {
+            return Ok(Vec::new());
+        }
+
+        // The footer holds the length of the whole table, itself included.
+        let at = end - ENTRY_HEADER_LEN;
+        let len = u16::from_le_bytes([image[at], image[at + 1]]) as usize;
+        let start = len
+            .checked_sub(ENTRY_HEADER_LEN)
+            .and_then(|len| at.checked_sub(len))
+            .ok_or(ErrorKind::InvalidData)?;
+
+        Self::entries(&image[start..at])
+    }
+
+    /// Parses GUID-tagged entries, starting at the end of `data`.
+    fn entries(mut data: &'a [u8]) -> Result<Vec<([u8; 16], &'a [u8])>> {
+        let mut entries = Vec::new();
+
+        while !data.is_empty() {
+            if data.len() < ENTRY_HEADER_LEN {
+                return Err(ErrorKind::InvalidData.into());
+            }
+
+            let at = data.len() - ENTRY_HEADER_LEN;
+            let mut guid = [0u8; 16];
+            guid.copy_from_slice(&data[at + 2..]);
+            let len = u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+
+            if len < ENTRY_HEADER_LEN || len > data.len() {
+                return Err(ErrorKind::InvalidData.into());
+            }
+
+            let start = data.len() - len;
+            entries.push((guid, &data[start..at]));
+            data = &data[..start];
+        }
+
+        Ok(entries)
+    }
+
+    fn metadata(image: &[u8], offset: usize) -> Result<Vec<Section>> {
+        let start = image
+            .len()
+            .checked_sub(offset)
+            .ok_or(ErrorKind::InvalidData)?;
+        let metadata = &image[start..];
+
+        if metadata.get(..4) != Some(&SNP_METADATA_SIGNATURE[..]) {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        // The count comes from the image, so bound it by the number of
+        // sections that fit before preallocating.
+        let count = u32_at(metadata, 12)? as usize;
+        if count > (metadata.len() - 16) / 12 {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        let mut sections = Vec::with_capacity(count);
+
+        for i in 0..count {
+            let at = 16 + i * 12;
+            let section = Section {
+                region: Region {
+                    gpa: u32_at(metadata, at)?,
+                    size: u32_at(metadata, at + 4)?,
+                },
+                section_type: SectionType::try_from(u32_at(metadata, at + 8)?)?,
+            };
+
+            if (section.region.gpa | section.region.size) & (PAGE_SIZE - 1) != 0 {
+                return Err(ErrorKind::InvalidData.into());
+            }
+
+            sections.push(section);
+        }
+
+        Ok(sections)
+    }
+
+    fn entry(&self, guid: &[u8; 16]) -> Option<&'a [u8]> {
+        self.table
+            .iter()
+            .find(|(g, _)| g == guid)
+            .map(|(_, data)| *data)
+    }
+
+    /// The raw firmware image.
+    pub fn image(&self) -> &'a [u8] {
+        self.image
+    }
+
+    /// The guest physical address at which the image is mapped, so that
+    /// it ends at 4 GiB.
+    pub fn gpa(&self) -> u64 {
+        FOUR_GIB - self.image.len() as u64
+    }
+
+    /// The address at which SEV-ES application processors start executing.
+    pub fn sev_es_reset_eip(&self) -> Result<Option<u32>> {
+        self.entry(&SEV_ES_RESET_BLOCK_GUID)
+            .map(|data| u32_at(data, 0))
+            .transpose()
+    }
+
+    /// The region reserved for the SEV kernel hash table.
+    pub fn sev_hash_table(&self) -> Result<Option<Region>> {
+        self.entry(&SEV_HASH_TABLE_GUID)
+            .map(Region::try_from)
+            .transpose()
+    }
+
+    /// The region reserved for the SEV launch secret.
+    pub fn sev_secret(&self) -> Result<Option<Region>> {
+        self.entry(&SEV_SECRET_GUID)
+            .map(Region::try_from)
+            .transpose()
+    }
+
+    /// The sections described by the SEV-SNP metadata, if any.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// The regions which must be passed to the SEV and SEV-ES
+    /// `update_data` in order: the firmware image itself, followed by the
+    /// kernel hash table (see [`HashTable`]) if `kernel_hashes` is set.
+    pub fn sev_regions(&self, kernel_hashes: bool) -> Result<Vec<Region>> {
+        let mut regions = vec![Region {
+            gpa: self.gpa() as u32,
+            size: self.image.len() as u32,
+        }];
+
+        if kernel_hashes {
+            let table = self.sev_hash_table()?.ok_or(ErrorKind::NotFound)?;
+            if (table.size as usize) < HashTable::LEN {
+                return Err(ErrorKind::InvalidData.into());
+            }
+
+            regions.push(Region {
+                gpa: table.gpa,
+                size: HashTable::LEN as u32,
+            });
+        }
+
+        Ok(regions)
+    }
+
+    /// The SEV-SNP launch updates for the firmware image and the sections
+    /// of the SEV-SNP metadata, in the order in which they must be issued.
+    ///
+    /// `memory` is called with the guest physical address and size of each
+    /// region and must return the host memory backing it: the guest memory
+    /// when launching a guest, or the expected contents when calculating a
+    /// measurement. If `kernel_hashes` is set, the hash table page is
+    /// measured as a normal page, and must hold the [`HashTable`] at the
+    /// offset of [`Ovmf::sev_hash_table`] within the page.
+    pub fn snp_updates<'b>(
+        &self,
+        kernel_hashes: bool,
+        mut memory: impl FnMut(u64, usize) -> &'b [u8],
+    ) -> Result<Vec<Update<'b>>> {
+        if self.sections.is_empty() {
+            return Err(ErrorKind::NotFound.into());
+        }
+
+        let perms = (VmplPerms::empty(), VmplPerms::empty(), VmplPerms::empty());
+        let mut updates = Vec::with_capacity(self.sections.len() + 1);
+
+        let gpa = self.gpa();
+        let image = memory(gpa, self.image.len());
+        updates.push(Update::new(
+            gpa >> 12,
+            image,
+            false,
+            PageType::Normal,
+            perms,
+        ));
+
+        for section in self.sections.iter() {
+            let gpa = u64::from(section.region.gpa);
+            let data = memory(gpa, section.region.size as usize);
+            let page_type = section.page_type(kernel_hashes);
+            updates.push(Update::new(gpa >> 12, data, false, page_type, perms));
+        }
+
+        Ok(updates)
+    }
+}
@@ -15,6 +15,8 @@ use std::mem::MaybeUninit;
 use std::os::unix::io::AsRawFd;
 
 use bitflags::bitflags;
+#[cfg(feature = "openssl")]
+use openssl::{hash, pkey, sign};
 use serde::{Deserialize, Serialize};
 
 /// Launcher type-state that indicates a brand new launch.
@@ -38,6 +40,11 @@ impl<'a, T, U: AsRawFd, V: AsRawFd> Launcher<'a, T, U, V> {
     pub fn as_mut_vmfd(&mut self) -> &mut U {
         self.vm_fd
     }
+
+    /// Query the status of the guest from the SEV firmware.
+    pub fn status(&mut self) -> Result<GuestStatus> {
+        GuestStatus::query(self.vm_fd, self.sev)
+    }
 }
 
 impl<'a, U: AsRawFd, V: AsRawFd> Launcher<'a, New, U, V> {
@@ -56,6 +63,25 @@ impl<'a, U: AsRawFd, V: AsRawFd> Launcher<'a, New, U, V> {
         Ok(launcher)
     }
 
+    /// Begin the SEV-ES launch process.
+    ///
+    /// The guest policy must require SEV-ES (see
+    /// [`PolicyFlags::ENCRYPTED_STATE`]).
+    pub fn new_es(kvm: &'a mut U, sev: &'a mut V) -> Result<Self> {
+        let launcher = Launcher {
+            vm_fd: kvm,
+            sev,
+            state: New,
+        };
+
+        let mut cmd = Command::from(launcher.sev, &EsInit);
+        ES_INIT
+            .ioctl(launcher.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Ok(launcher)
+    }
+
     /// Create an encrypted guest context.
     pub fn start(self, start: Start) -> Result<Launcher<'a, Started, U, V>> {
         let mut launch_start = LaunchStart::new(&start.policy, &start.cert, &start.session);
@@ -89,6 +115,20 @@ impl<'a, U: AsRawFd, V: AsRawFd> Launcher<'a, Started, U, V> {
         Ok(())
     }
 
+    /// Encrypt the VMSAs of all vCPUs of an SEV-ES guest with its VEK.
+    ///
+    /// All vCPUs must have been created and their initial register state
+    /// set beforehand, since it can no longer be changed afterwards. The
+    /// VMSAs are measured after all data, in vCPU order.
+    pub fn update_vmsa(&mut self) -> Result<()> {
+        let mut cmd = Command::from(self.sev, &LaunchUpdateVmsa);
+        LAUNCH_UPDATE_VMSA
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Ok(())
+    }
+
     /// Request a measurement from the SEV firmware.
     pub fn measure(self) -> Result<Launcher<'a, Measured, U, V>> {
         let mut measurement = MaybeUninit::uninit();
@@ -114,6 +154,15 @@ impl<'a, U: AsRawFd, V: AsRawFd> Launcher<'a, Measured, U, V> {
         self.state.1
     }
 
+    /// Get an attestation report of the guest, signed by the PEK of the
+    /// platform.
+    ///
+    /// The `mnonce` is chosen by the caller and included in the report to
+    /// guarantee its freshness. This requires SEV firmware 0.23 or later.
+    pub fn report(&mut self, mnonce: [u8; 16]) -> Result<AttestationReport> {
+        AttestationReport::query(self.vm_fd, self.sev, mnonce)
+    }
+
     /// Inject a secret into the guest.
     ///
     /// ## Remarks
@@ -138,6 +187,243 @@ impl<'a, U: AsRawFd, V: AsRawFd> Launcher<'a, Measured, U, V> {
     }
 }
 
+/// The handle of a guest context in the SEV firmware.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Handle(u32);
+
+impl From<u32> for Handle {
+    fn from(handle: u32) -> Self {
+        Self(handle)
+    }
+}
+
+impl From<Handle> for u32 {
+    fn from(handle: Handle) -> Self {
+        handle.0
+    }
+}
+
+impl std::fmt::Display for Handle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Handle {
+    /// Query the status of the guest behind this handle.
+    ///
+    /// Fails if `vm_fd` does not belong to the guest with this handle.
+    pub fn status<U: AsRawFd, V: AsRawFd>(
+        &self,
+        vm_fd: &mut U,
+        sev: &mut V,
+    ) -> Result<GuestStatus> {
+        let status = GuestStatus::query(vm_fd, sev)?;
+        if status.handle != *self {
+            return Err(std::io::ErrorKind::InvalidInput.into());
+        }
+
+        Ok(status)
+    }
+
+    /// Get an attestation report of the (running) guest behind this
+    /// handle, signed by the PEK of the platform.
+    ///
+    /// The `mnonce` is chosen by the caller and included in the report to
+    /// guarantee its freshness. This requires SEV firmware 0.23 or later.
+    /// Fails if `vm_fd` does not belong to the guest with this handle.
+    pub fn report<U: AsRawFd, V: AsRawFd>(
+        &self,
+        vm_fd: &mut U,
+        sev: &mut V,
+        mnonce: [u8; 16],
+    ) -> Result<AttestationReport> {
+        self.status(vm_fd, sev)?;
+        AttestationReport::query(vm_fd, sev, mnonce)
+    }
+}
+
+/// The state of a guest context in the SEV firmware.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[repr(u8)]
+pub enum GuestState {
+    /// The guest is uninitialized.
+    Uninitialized,
+
+    /// The guest is being launched and accepts data.
+    LaunchUpdate,
+
+    /// The guest has been measured and accepts secrets.
+    LaunchSecret,
+
+    /// The guest is running.
+    Running,
+
+    /// The guest is being sent to another platform.
+    SendUpdate,
+
+    /// The guest is being received from another platform.
+    ReceiveUpdate,
+
+    /// The guest has been sent to another platform.
+    Sent,
+}
+
+impl std::fmt::Display for GuestState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self {
+            GuestState::Uninitialized => "uninitialized",
+            GuestState::LaunchUpdate => "launch update",
+            GuestState::LaunchSecret => "launch secret",
+            GuestState::Running => "running",
+            GuestState::SendUpdate => "send update",
+            GuestState::ReceiveUpdate => "receive update",
+            GuestState::Sent => "sent",
+        };
+        write!(f, "{}", state)
+    }
+}
+
+/// The status of a guest as reported by the SEV firmware.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GuestStatus {
+    /// The handle of the guest.
+    pub handle: Handle,
+
+    /// The policy of the guest.
+    pub policy: Policy,
+
+    /// The state of the guest.
+    pub state: GuestState,
+}
+
+impl GuestStatus {
+    fn query<U: AsRawFd, V: AsRawFd>(vm_fd: &mut U, sev: &mut V) -> Result<Self> {
+        let mut guest_status = GetGuestStatus::default();
+        let mut cmd = Command::from_mut(sev, &mut guest_status);
+        GUEST_STATUS
+            .ioctl(vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Ok(Self {
+            handle: guest_status.handle(),
+            policy: guest_status.policy(),
+            state: match guest_status.state() {
+                0 => GuestState::Uninitialized,
+                1 => GuestState::LaunchUpdate,
+                2 => GuestState::LaunchSecret,
+                3 => GuestState::Running,
+                4 => GuestState::SendUpdate,
+                5 => GuestState::ReceiveUpdate,
+                6 => GuestState::Sent,
+                _ => return Err(std::io::ErrorKind::InvalidData.into()),
+            },
+        })
+    }
+}
+
+/// A block of memory as encrypted by the firmware.
+#[repr(C, align(16))]
+#[derive(Copy, Clone)]
+struct Block([u8; 16]);
+
+/// Reads and writes the memory of an SEV guest whose policy allows
+/// debugging, using the DBG_DECRYPT and DBG_ENCRYPT commands.
+///
+/// Guest memory is addressed by its address in the address space of the
+/// VMM. The firmware only operates on 16-byte aligned blocks, so unaligned
+/// accesses are widened to the surrounding blocks.
+pub struct GuestMemoryDebugger<'a, U: AsRawFd, V: AsRawFd> {
+    vm_fd: &'a mut U,
+    sev: &'a mut V,
+}
+
+impl<'a, U: AsRawFd, V: AsRawFd> GuestMemoryDebugger<'a, U, V> {
+    /// The alignment required by the firmware.
+    const ALIGN: usize = 16;
+
+    /// Create a debugger for the guest of `vm_fd`.
+    ///
+    /// The policy of the guest is queried from the firmware. Fails if it
+    /// forbids debugging the guest (see [`PolicyFlags::NO_DEBUG`]).
+    pub fn new(vm_fd: &'a mut U, sev: &'a mut V) -> Result<Self> {
+        let status = GuestStatus::query(vm_fd, sev)?;
+        if status.policy.flags.contains(PolicyFlags::NO_DEBUG) {
+            return Err(std::io::ErrorKind::PermissionDenied.into());
+        }
+
+        Ok(Self { vm_fd, sev })
+    }
+
+    /// Decrypt the guest memory at `addr` into `buf`.
+    pub fn read(&mut self, addr: usize, buf: &mut [u8]) -> Result<()> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        let (start, mut bounce) = Self::bounce(addr, buf.len())?;
+        self.decrypt(start, Self::bytes(&mut bounce))?;
+
+        buf.copy_from_slice(&Self::bytes(&mut bounce)[addr - start..][..buf.len()]);
+        Ok(())
+    }
+
+    /// Encrypt `data` into the guest memory at `addr`.
+    ///
+    /// The guest memory surrounding an unaligned write is read back first,
+    /// so that it is preserved.
+    pub fn write(&mut self, addr: usize, data: &[u8]) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let (start, mut bounce) = Self::bounce(addr, data.len())?;
+        if start != addr || data.len() & (Self::ALIGN - 1) != 0 {
+            self.decrypt(start, Self::bytes(&mut bounce))?;
+        }
+
+        Self::bytes(&mut bounce)[addr - start..][..data.len()].copy_from_slice(data);
+        self.encrypt(Self::bytes(&mut bounce), start)
+    }
+
+    fn decrypt(&mut self, src: usize, dst: &mut [u8]) -> Result<()> {
+        let dbg_decrypt = DbgDecrypt::new(src, dst);
+        let mut cmd = Command::from(self.sev, &dbg_decrypt);
+        DBG_DECRYPT
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+        Ok(())
+    }
+
+    fn encrypt(&mut self, src: &[u8], dst: usize) -> Result<()> {
+        let dbg_encrypt = DbgEncrypt::new(src, dst);
+        let mut cmd = Command::from(self.sev, &dbg_encrypt);
+        DBG_ENCRYPT
+            .ioctl(self.vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+        Ok(())
+    }
+
+    /// The aligned start address and an aligned bounce buffer covering
+    /// `len` bytes at `addr`.
+    fn bounce(addr: usize, len: usize) -> Result<(usize, Vec<Block>)> {
+        let end = addr
+            .checked_add(len)
+            .and_then(|end| end.checked_add(Self::ALIGN - 1))
+            .ok_or(std::io::ErrorKind::InvalidInput)?
+            & !(Self::ALIGN - 1);
+        let start = addr & !(Self::ALIGN - 1);
+
+        Ok((start, vec![Block([0; 16]); (end - start) / Self::ALIGN]))
+    }
+
+    fn bytes(bounce: &mut [Block]) -> &mut [u8] {
+        let len = std::mem::size_of_val(bounce);
+        unsafe { std::slice::from_raw_parts_mut(bounce.as_mut_ptr() as *mut u8, len) }
+    }
+}
+
 bitflags! {
     /// Configurable SEV Policy options.
     #[derive(Default, Deserialize, Serialize)]
@@ -172,24 +458,116 @@ pub struct Policy {
     /// The various policy optons are encoded as bit flags.
     pub flags: PolicyFlags,
 
-    /// The desired minimum platform firmware version.
+    /// The desired minimum platform firmware API version. The major
+    /// version is encoded in bits 16 to 23 of the policy and the minor
+    /// version in bits 24 to 31.
     pub minfw: Version,
 }
 
+#[cfg(feature = "openssl")]
+impl Policy {
+    pub(crate) fn bytes(self) -> [u8; 4] {
+        u32::from(self).to_le_bytes()
+    }
+}
+
+impl Policy {
+    /// Convert a policy represented as a u32 to a Policy struct, dropping
+    /// any flag bits this crate does not know of.
+    ///
+    /// Use this for policies reported by the firmware, which may set flags
+    /// introduced after this crate was written. Use [`Policy::try_from`]
+    /// to validate a policy provided by a user.
+    pub fn from_bits_truncate(p: u32) -> Self {
+        Self {
+            flags: PolicyFlags::from_bits_truncate(p as u16),
+            minfw: Version {
+                major: (p >> 16) as u8,
+                minor: (p >> 24) as u8,
+            },
+        }
+    }
+
+    /// Check whether the platform with the given status can satisfy
+    /// this policy.
+    pub fn check(&self, status: &firmware::Status) -> std::result::Result<(), PolicyError> {
+        if self.flags.contains(PolicyFlags::ENCRYPTED_STATE)
+            && !status
+                .flags
+                .contains(firmware::PlatformStatusFlags::ENCRYPTED_STATE)
+        {
+            return Err(PolicyError::EncryptedStateUnsupported);
+        }
+
+        if self.minfw > status.build.version {
+            return Err(PolicyError::FirmwareTooOld {
+                required: self.minfw,
+                installed: status.build.version,
+            });
+        }
+
+        Ok(())
+    }
+}
+
 /// Convert a policy represented as a u32 to a Policy struct.
-impl From<u32> for Policy {
-    fn from(p: u32) -> Self {
-        let flags = p as u16;
-        let flags = PolicyFlags::from_bits_truncate(flags);
+///
+/// Fails if any reserved flag bit is set.
+impl TryFrom<u32> for Policy {
+    type Error = std::io::Error;
 
-        let p = p >> 16;
-        let p = p as u16;
-        let minfw = Version::from(p);
+    fn try_from(p: u32) -> Result<Self> {
+        PolicyFlags::from_bits(p as u16).ok_or(std::io::ErrorKind::InvalidData)?;
+        Ok(Self::from_bits_truncate(p))
+    }
+}
 
-        Self { flags, minfw }
+/// Encode a policy as the u32 used by the SEV firmware.
+impl From<Policy> for u32 {
+    fn from(policy: Policy) -> Self {
+        u32::from(policy.flags.bits())
+            | u32::from(policy.minfw.major) << 16
+            | u32::from(policy.minfw.minor) << 24
     }
 }
 
+/// The reasons a platform cannot satisfy a policy.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PolicyError {
+    /// The policy requires SEV-ES, which the platform does not support.
+    EncryptedStateUnsupported,
+
+    /// The policy requires a newer firmware API version than the one
+    /// installed on the platform.
+    FirmwareTooOld {
+        /// The minimum version required by the policy.
+        required: Version,
+
+        /// The version installed on the platform.
+        installed: Version,
+    },
+}
+
+impl std::fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyError::EncryptedStateUnsupported => {
+                write!(f, "SEV-ES is required but not supported by the platform")
+            }
+            PolicyError::FirmwareTooOld {
+                required,
+                installed,
+            } => write!(
+                f,
+                "firmware {} is required but {} is installed",
+                required, installed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
 /// A secure channel between the tenant and the AMD Secure
 /// Processor.
 #[repr(C)]
@@ -328,3 +706,153 @@ impl codicon::Encoder<()> for Measurement {
         writer.save(self)
     }
 }
+
+/// The ECDSA signature of an attestation report.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Signature {
+    /// The R component, in little-endian byte order.
+    #[serde(with = "crate::util::array")]
+    pub r: [u8; 72],
+
+    /// The S component, in little-endian byte order.
+    #[serde(with = "crate::util::array")]
+    pub s: [u8; 72],
+}
+
+/// An attestation report of a running SEV guest, signed by the PEK.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AttestationReport {
+    /// The nonce provided by the caller.
+    pub mnonce: [u8; 16],
+
+    /// The launch digest of the guest.
+    pub launch_digest: [u8; 32],
+
+    /// The policy of the guest.
+    pub policy: Policy,
+
+    /// The usage of the key that signed the report (the PEK).
+    pub sig_usage: u32,
+
+    /// The algorithm used to sign the report.
+    pub sig_algo: u32,
+
+    #[serde(skip)]
+    _reserved: u32,
+
+    /// The signature over the bytes of the report preceding `sig_usage`.
+    pub signature: Signature,
+}
+
+impl AttestationReport {
+    /// The number of leading bytes of the report covered by its signature.
+    pub const SIGNED_LEN: usize = 0x34;
+
+    fn query<U: AsRawFd, V: AsRawFd>(vm_fd: &mut U, sev: &mut V, mnonce: [u8; 16]) -> Result<Self> {
+        let mut report = MaybeUninit::uninit();
+        let mut get_report = GetAttestationReport::new(mnonce, &mut report);
+        let mut cmd = Command::from_mut(sev, &mut get_report);
+        GET_ATTESTATION_REPORT
+            .ioctl(vm_fd, &mut cmd)
+            .map_err(|e| cmd.encapsulate(e))?;
+
+        Ok(unsafe { report.assume_init() })
+    }
+}
+
+impl codicon::Decoder<()> for AttestationReport {
+    type Error = std::io::Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> std::io::Result<Self> {
+        reader.load()
+    }
+}
+
+impl codicon::Encoder<()> for AttestationReport {
+    type Error = std::io::Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> std::io::Result<()> {
+        writer.save(self)
+    }
+}
+
+/// Calculates the launch digest of an SEV or SEV-ES guest ahead of time,
+/// without the need for SEV hardware.
+///
+/// The data passed to `Launcher<Started>::update_data` (and, for SEV-ES
+/// guests, the VMSAs of the vCPUs) must be replayed in the same order. The
+/// expected `Measurement.measure` the AMD SP will report can then be
+/// calculated from the digest with [`LaunchDigest::measure`].
+#[cfg(feature = "openssl")]
+pub struct LaunchDigest {
+    policy: Policy,
+    hasher: hash::Hasher,
+}
+
+#[cfg(feature = "openssl")]
+impl LaunchDigest {
+    /// Begin calculating the launch digest of a guest with the given policy.
+    pub fn new(policy: Policy) -> Result<Self> {
+        Ok(Self {
+            policy,
+            hasher: hash::Hasher::new(hash::MessageDigest::sha256())?,
+        })
+    }
+
+    /// Include guest data in the digest.
+    pub fn update_data(&mut self, data: &[u8]) -> Result<()> {
+        Ok(self.hasher.update(data)?)
+    }
+
+    /// Include the VMSA of a vCPU in the digest.
+    ///
+    /// The VMSAs are only measured for SEV-ES guests, so this fails unless
+    /// the policy requires SEV-ES.
+    pub fn update_vmsa(&mut self, vmsa: &[u8]) -> Result<()> {
+        if !self.policy.flags.contains(PolicyFlags::ENCRYPTED_STATE) {
+            return Err(std::io::ErrorKind::InvalidInput.into());
+        }
+
+        Ok(self.hasher.update(vmsa)?)
+    }
+
+    /// The raw launch digest of the data measured so far.
+    pub fn digest(&self) -> Result<[u8; 32]> {
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&self.hasher.clone().finish()?);
+        Ok(digest)
+    }
+
+    /// The `Measurement.measure` value the AMD SP will report for the data
+    /// measured so far, given the firmware build, the TIK of the launch
+    /// session and the nonce chosen by the AMD SP.
+    pub fn measure(&self, build: Build, tik: &[u8], mnonce: [u8; 16]) -> Result<[u8; 32]> {
+        measure(&self.digest()?, self.policy, build, tik, mnonce)
+    }
+}
+
+/// Calculates the HMAC over a launch digest that the AMD SP reports as
+/// `Measurement.measure`.
+#[cfg(feature = "openssl")]
+pub(crate) fn measure(
+    digest: &[u8],
+    policy: Policy,
+    build: Build,
+    tik: &[u8],
+    mnonce: [u8; 16],
+) -> Result<[u8; 32]> {
+    let key = pkey::PKey::hmac(tik)?;
+    let mut sig = sign::Signer::new(hash::MessageDigest::sha256(), &key)?;
+
+    sig.update(&[0x04u8])?;
+    sig.update(&[build.version.major, build.version.minor, build.build])?;
+    sig.update(&policy.bytes())?;
+    sig.update(digest)?;
+    sig.update(&mnonce)?;
+
+    let mut measure = [0u8; 32];
+    sig.sign(&mut measure)?;
+    Ok(measure)
+}
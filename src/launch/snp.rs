@@ -4,17 +4,31 @@
 //! This ensures (at compile time) that the right steps are called in the
 //! right order.
 
+use crate::firmware::guest::Signature;
 #[cfg(target_os = "linux")]
 use crate::launch::linux::ioctl::*;
 #[cfg(target_os = "linux")]
 use crate::launch::linux::snp::*;
+use crate::util::*;
 use crate::Version;
 
-use std::io::Result;
+use std::convert::TryFrom;
+#[cfg(feature = "openssl")]
+use std::io::ErrorKind;
+use std::io::{Read, Result, Write};
 use std::marker::PhantomData;
 use std::os::unix::io::AsRawFd;
 
 use bitflags::bitflags;
+#[cfg(feature = "openssl")]
+use openssl::{
+    bn::{BigNum, BigNumContext, BigNumRef},
+    ec::{EcKey, EcKeyRef},
+    ecdsa::EcdsaSig,
+    nid::Nid,
+    pkey::{HasPublic, Private},
+    sha::sha384,
+};
 use serde::{Deserialize, Serialize};
 
 /// Launcher type-state that indicates a brand new launch.
@@ -113,54 +127,124 @@ impl<U: AsRawFd, V: AsRawFd> Launcher<Started, U, V> {
 
 bitflags! {
     /// Configurable SNP Policy options.
+    ///
+    /// The flags occupy bits 16 and up of the 64-bit guest policy.
     #[derive(Default, Deserialize, Serialize)]
     pub struct PolicyFlags: u16 {
         /// Enable if SMT is enabled in the host machine.
         const SMT = 1;
 
+        /// Reserved, must be one. It is always set when encoding a policy
+        /// and dropped when decoding one.
+        const RESERVED_1 = 1 << 1;
+
         /// If enabled, association with a migration agent is allowed.
         const MIGRATE_MA = 1 << 2;
 
         /// If enabled, debugging is allowed.
         const DEBUG = 1 << 3;
+
+        /// If enabled, the guest can only be activated on one socket.
+        const SINGLE_SOCKET = 1 << 4;
+
+        /// If enabled, CXL can be populated with devices or memory.
+        const CXL_ALLOW = 1 << 5;
+
+        /// If enabled, AES-256-XTS is required for memory encryption.
+        const MEM_AES_256_XTS = 1 << 6;
+
+        /// If enabled, Running Average Power Limit (RAPL) must be disabled.
+        const RAPL_DIS = 1 << 7;
+
+        /// If enabled, ciphertext hiding must be enabled.
+        const CIPHERTEXT_HIDING = 1 << 8;
+
+        /// If enabled, the hypervisor may not swap out the guest's pages.
+        const PAGE_SWAP_DISABLE = 1 << 9;
     }
 }
 
 /// Describes a policy that the AMD Secure Processor will
 /// enforce.
+///
+/// The same type describes the policy a guest is launched with and the
+/// policy found in its attestation reports (see
+/// [`AttestationReport::guest_policy`](crate::firmware::guest::AttestationReport::guest_policy)).
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Policy {
     /// The various policy optons are encoded as bit flags.
     pub flags: PolicyFlags,
 
-    /// The desired minimum platform firmware version.
+    /// The minimum ABI version of the firmware required to run the guest.
     pub minfw: Version,
 }
 
 impl From<Policy> for u64 {
     fn from(policy: Policy) -> u64 {
-        let mut val: u64 = 0;
-
         let minor_version = u64::from(policy.minfw.minor);
-        let mut major_version = u64::from(policy.minfw.major);
+        let major_version = u64::from(policy.minfw.major);
 
         /*
          * According to the SNP firmware spec, bit 1 of the policy flags is reserved and must
          * always be set to 1. Rather than passing this responsibility off to callers, set this bit
          * every time an ioctl is issued to the kernel.
          */
-        let flags = policy.flags.bits | 0b10;
-        let mut flags_64 = u64::from(flags);
+        let flags = u64::from((policy.flags | PolicyFlags::RESERVED_1).bits());
 
-        major_version <<= 8;
-        flags_64 <<= 16;
+        minor_version | major_version << 8 | flags << 16
+    }
+}
 
-        val |= minor_version;
-        val |= major_version;
-        val |= flags_64;
-        val &= 0x00FFFFFF;
+/// Decode a 64-bit guest policy.
+///
+/// Fails if any reserved bit is set, or if the bit which must be one is
+/// clear.
+impl TryFrom<u64> for Policy {
+    type Error = std::io::Error;
+
+    fn try_from(value: u64) -> Result<Self> {
+        let bits = value >> 16;
+        let flags = u16::try_from(bits)
+            .ok()
+            .and_then(PolicyFlags::from_bits)
+            .ok_or(std::io::ErrorKind::InvalidData)?;
+
+        if !flags.contains(PolicyFlags::RESERVED_1) {
+            return Err(std::io::ErrorKind::InvalidData.into());
+        }
 
-        val
+        Ok(Self {
+            flags: flags - PolicyFlags::RESERVED_1,
+            minfw: Version {
+                major: (value >> 8) as u8,
+                minor: value as u8,
+            },
+        })
+    }
+}
+
+impl std::fmt::Display for Policy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const NAMES: [(PolicyFlags, &str); 9] = [
+            (PolicyFlags::SMT, "SMT allowed"),
+            (PolicyFlags::MIGRATE_MA, "migration agent allowed"),
+            (PolicyFlags::DEBUG, "debugging allowed"),
+            (PolicyFlags::SINGLE_SOCKET, "single socket"),
+            (PolicyFlags::CXL_ALLOW, "CXL allowed"),
+            (PolicyFlags::MEM_AES_256_XTS, "AES-256-XTS required"),
+            (PolicyFlags::RAPL_DIS, "RAPL disabled"),
+            (PolicyFlags::CIPHERTEXT_HIDING, "ciphertext hiding required"),
+            (PolicyFlags::PAGE_SWAP_DISABLE, "page swapping disabled"),
+        ];
+
+        write!(f, "ABI >= {}.{}", self.minfw.major, self.minfw.minor)?;
+        for (flag, name) in NAMES.iter() {
+            if self.flags.contains(*flag) {
+                write!(f, ", {}", name)?;
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -238,6 +322,84 @@ impl<'a> Update<'a> {
     }
 }
 
+/// The size of a guest page.
+#[cfg(feature = "openssl")]
+const PAGE_SIZE: usize = 4096;
+
+/// The size of the PAGE_INFO structure hashed for every page.
+#[cfg(feature = "openssl")]
+const PAGE_INFO_LEN: usize = 0x70;
+
+/// Calculates the SEV-SNP launch digest (MEASUREMENT) of a guest ahead of
+/// time, without the need for SEV-SNP hardware.
+///
+/// Every `Update` passed to `Launcher<Started>::update_data` must be replayed
+/// in the same order through [`Measurement::update`]. The firmware extends
+/// the digest once per page with the SHA-384 of a PAGE_INFO structure
+/// containing the current digest, the page contents (for normal and VMSA
+/// pages), the page type, the IMI flag, the VMPL permissions and the guest
+/// physical address.
+#[cfg(feature = "openssl")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Measurement {
+    digest: [u8; 48],
+}
+
+#[cfg(feature = "openssl")]
+impl Default for Measurement {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl Measurement {
+    /// Begin a new measurement.
+    pub fn new() -> Self {
+        Self { digest: [0u8; 48] }
+    }
+
+    /// Extend the measurement with the pages of an update.
+    ///
+    /// The length of the update must be a multiple of the page size.
+    pub fn update(&mut self, update: &Update) -> Result<()> {
+        let pages = update.uaddr.chunks_exact(PAGE_SIZE);
+        if !pages.remainder().is_empty() {
+            return Err(std::io::ErrorKind::InvalidInput.into());
+        }
+
+        for (i, page) in pages.enumerate() {
+            let gpa = (update.start_gfn + i as u64) << 12;
+
+            let contents = match update.page_type {
+                PageType::Normal | PageType::Vmsa => sha384(page),
+                _ => [0u8; 48],
+            };
+
+            let mut info = [0u8; PAGE_INFO_LEN];
+            info[0x00..0x30].copy_from_slice(&self.digest);
+            info[0x30..0x60].copy_from_slice(&contents);
+            info[0x60..0x62].copy_from_slice(&(PAGE_INFO_LEN as u16).to_le_bytes());
+            info[0x62] = update.page_type as u8;
+            info[0x63] = update.imi_page as u8;
+            info[0x64] = update.vmpl3_perms.bits();
+            info[0x65] = update.vmpl2_perms.bits();
+            info[0x66] = update.vmpl1_perms.bits();
+            info[0x68..0x70].copy_from_slice(&gpa.to_le_bytes());
+
+            self.digest = sha384(&info);
+        }
+
+        Ok(())
+    }
+
+    /// The current launch digest, as reported in the `measurement` field
+    /// of the guest's attestation reports.
+    pub fn digest(&self) -> [u8; 48] {
+        self.digest
+    }
+}
+
 bitflags! {
     #[derive(Default, Deserialize, Serialize)]
     /// VMPL permission masks.
@@ -274,13 +436,315 @@ pub enum PageType {
     /// A page that is encrypted but not measured
     Unmeasured = 0x4,
 
-    /// A page for the firmware to store secrets for the guest.
+    /// A page for the firmware to store secrets for the guest (see
+    /// [`SecretsPage`](crate::firmware::guest::SecretsPage)).
     Secrets = 0x5,
 
     /// A page for the hypervisor to provide CPUID function values.
     Cpuid = 0x6,
 }
 
+/// The version of the ID block structure.
+const ID_BLOCK_VERSION: u32 = 1;
+
+/// The ECDSA P-384 with SHA-384 algorithm for ID and author keys.
+#[cfg(feature = "openssl")]
+const ID_ALGO_ECDSA_P384_SHA384: u32 = 1;
+
+/// The P-384 curve of an ID or author public key.
+#[cfg(feature = "openssl")]
+const ID_CURVE_P384: u32 = 2;
+
+/// Identifies a guest image and the launch digest and policy it must be
+/// launched with. The ID block is signed by the ID key of the image owner
+/// and checked by the firmware on SNP_LAUNCH_FINISH.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IdBlock {
+    /// The expected launch digest of the guest.
+    #[serde(with = "crate::util::array")]
+    pub launch_digest: [u8; 48],
+
+    /// The family ID of the guest, provided by the image owner.
+    pub family_id: [u8; 16],
+
+    /// The image ID of the guest, provided by the image owner.
+    pub image_id: [u8; 16],
+
+    /// The version of the ID block structure.
+    pub version: u32,
+
+    /// The security version number of the guest.
+    pub guest_svn: u32,
+
+    /// The policy the guest must be launched with.
+    pub policy: u64,
+}
+
+impl IdBlock {
+    /// Describe a guest image.
+    pub fn new(
+        launch_digest: [u8; 48],
+        family_id: [u8; 16],
+        image_id: [u8; 16],
+        guest_svn: u32,
+        policy: Policy,
+    ) -> Self {
+        Self {
+            launch_digest,
+            family_id,
+            image_id,
+            version: ID_BLOCK_VERSION,
+            guest_svn,
+            policy: policy.into(),
+        }
+    }
+}
+
+impl codicon::Decoder<()> for IdBlock {
+    type Error = std::io::Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> Result<Self> {
+        reader.load()
+    }
+}
+
+impl codicon::Encoder<()> for IdBlock {
+    type Error = std::io::Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> Result<()> {
+        writer.save(self)
+    }
+}
+
+/// An ECDSA P-384 public key, as found in the authentication information
+/// of an ID block.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IdPublicKey {
+    /// The curve of the key (2 for P-384).
+    pub curve: u32,
+
+    /// The x coordinate of the public point, in little-endian.
+    #[serde(with = "crate::util::array")]
+    pub qx: [u8; 72],
+
+    /// The y coordinate of the public point, in little-endian.
+    #[serde(with = "crate::util::array")]
+    pub qy: [u8; 72],
+
+    #[serde(with = "crate::util::array")]
+    _reserved: [u8; 880],
+}
+
+impl Default for IdPublicKey {
+    fn default() -> Self {
+        Self {
+            curve: 0,
+            qx: [0u8; 72],
+            qy: [0u8; 72],
+            _reserved: [0u8; 880],
+        }
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl IdPublicKey {
+    /// The SHA-384 digest of the key, as reported in the `id_key_digest`
+    /// and `author_key_digest` fields of the guest's attestation reports.
+    pub fn digest(&self) -> Result<[u8; 48]> {
+        let mut bytes = Vec::with_capacity(std::mem::size_of::<Self>());
+        bytes.save(self)?;
+        Ok(sha384(&bytes))
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl<T: HasPublic> TryFrom<&EcKeyRef<T>> for IdPublicKey {
+    type Error = std::io::Error;
+
+    fn try_from(value: &EcKeyRef<T>) -> Result<Self> {
+        let g = value.group();
+        if g.curve_name() != Some(Nid::SECP384R1) {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+
+        let mut c = BigNumContext::new()?;
+        let mut x = BigNum::new()?;
+        let mut y = BigNum::new()?;
+        value
+            .public_key()
+            .affine_coordinates_gfp(g, &mut x, &mut y, &mut c)?;
+
+        Ok(Self {
+            curve: ID_CURVE_P384,
+            qx: le_bytes(&x),
+            qy: le_bytes(&y),
+            ..Default::default()
+        })
+    }
+}
+
+/// The authentication information of an ID block: the signature of the ID
+/// block by the ID key and, optionally, the signature of the ID key by an
+/// author key.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IdAuth {
+    /// The algorithm of the ID key (1 for ECDSA P-384 with SHA-384).
+    pub id_key_algo: u32,
+
+    /// The algorithm of the author key, or 0 if there is none.
+    pub author_key_algo: u32,
+
+    #[serde(with = "crate::util::array")]
+    _reserved_0: [u8; 56],
+
+    /// The signature of the ID block by the ID key.
+    pub id_block_sig: Signature,
+
+    /// The ID key.
+    pub id_key: IdPublicKey,
+
+    #[serde(with = "crate::util::array")]
+    _reserved_1: [u8; 60],
+
+    /// The signature of the ID key by the author key.
+    pub id_key_sig: Signature,
+
+    /// The author key.
+    pub author_key: IdPublicKey,
+
+    #[serde(with = "crate::util::array")]
+    _reserved_2: [u8; 892],
+}
+
+impl Default for IdAuth {
+    fn default() -> Self {
+        Self {
+            id_key_algo: 0,
+            author_key_algo: 0,
+            _reserved_0: [0u8; 56],
+            id_block_sig: Signature::default(),
+            id_key: IdPublicKey::default(),
+            _reserved_1: [0u8; 60],
+            id_key_sig: Signature::default(),
+            author_key: IdPublicKey::default(),
+            _reserved_2: [0u8; 892],
+        }
+    }
+}
+
+impl codicon::Decoder<()> for IdAuth {
+    type Error = std::io::Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> Result<Self> {
+        reader.load()
+    }
+}
+
+impl codicon::Encoder<()> for IdAuth {
+    type Error = std::io::Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> Result<()> {
+        writer.save(self)
+    }
+}
+
+/// Signs ID blocks with the ECDSA P-384 ID key of an image owner and,
+/// optionally, certifies the ID key with an author key.
+#[cfg(feature = "openssl")]
+pub struct IdSigner {
+    id_key: EcKey<Private>,
+    author_key: Option<EcKey<Private>>,
+}
+
+#[cfg(feature = "openssl")]
+impl IdSigner {
+    /// Create a signer for the given ID key, which must be on the P-384
+    /// curve.
+    pub fn new(id_key: EcKey<Private>) -> Result<Self> {
+        IdPublicKey::try_from(&*id_key)?;
+
+        Ok(Self {
+            id_key,
+            author_key: None,
+        })
+    }
+
+    /// Certify the ID key with the given author key, which must be on the
+    /// P-384 curve.
+    pub fn with_author_key(mut self, author_key: EcKey<Private>) -> Result<Self> {
+        IdPublicKey::try_from(&*author_key)?;
+
+        self.author_key = Some(author_key);
+        Ok(self)
+    }
+
+    /// The digest of the ID key, as reported in the `id_key_digest` field
+    /// of the guest's attestation reports.
+    pub fn id_key_digest(&self) -> Result<[u8; 48]> {
+        IdPublicKey::try_from(&*self.id_key)?.digest()
+    }
+
+    /// The digest of the author key, as reported in the
+    /// `author_key_digest` field of the guest's attestation reports.
+    /// Guests launched without an author key report zeroes.
+    pub fn author_key_digest(&self) -> Result<[u8; 48]> {
+        match &self.author_key {
+            Some(key) => IdPublicKey::try_from(&**key)?.digest(),
+            None => Ok([0u8; 48]),
+        }
+    }
+
+    /// Sign an ID block, producing its authentication information.
+    pub fn sign(&self, id_block: &IdBlock) -> Result<IdAuth> {
+        let mut bytes = Vec::with_capacity(std::mem::size_of::<IdBlock>());
+        bytes.save(id_block)?;
+
+        let mut auth = IdAuth {
+            id_key_algo: ID_ALGO_ECDSA_P384_SHA384,
+            id_block_sig: ecdsa_sign(&bytes, &self.id_key)?,
+            id_key: IdPublicKey::try_from(&*self.id_key)?,
+            ..Default::default()
+        };
+
+        if let Some(author_key) = &self.author_key {
+            let mut bytes = Vec::with_capacity(std::mem::size_of::<IdPublicKey>());
+            bytes.save(&auth.id_key)?;
+
+            auth.author_key_algo = ID_ALGO_ECDSA_P384_SHA384;
+            auth.id_key_sig = ecdsa_sign(&bytes, author_key)?;
+            auth.author_key = IdPublicKey::try_from(&**author_key)?;
+        }
+
+        Ok(auth)
+    }
+}
+
+/// Sign the SHA-384 digest of `data`.
+#[cfg(feature = "openssl")]
+fn ecdsa_sign(data: &[u8], key: &EcKeyRef<Private>) -> Result<Signature> {
+    let sig = EcdsaSig::sign(&sha384(data), key)?;
+
+    let mut signature = Signature::default();
+    signature.r = le_bytes(sig.r());
+    signature.s = le_bytes(sig.s());
+    Ok(signature)
+}
+
+/// Encode a number as 72 little-endian bytes.
+#[cfg(feature = "openssl")]
+fn le_bytes(value: &BigNumRef) -> [u8; 72] {
+    let mut buf = [0u8; 72];
+
+    for (i, b) in value.to_vec().iter().rev().cloned().enumerate() {
+        buf[i] = b;
+    }
+
+    buf
+}
+
 /// Encapsulates the data needed to complete a guest launch.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Finish<'a, 'b> {
@@ -297,6 +761,9 @@ pub struct Finish<'a, 'b> {
 
 impl<'a, 'b> Finish<'a, 'b> {
     /// Encapsulate all data needed for the SNP_LAUNCH_FINISH ioctl.
+    ///
+    /// The author key is used if `id_auth` declares an author key
+    /// algorithm.
     pub fn new(
         id_block: Option<&'a [u8]>,
         id_auth: Option<&'b [u8]>,
@@ -308,4 +775,28 @@ impl<'a, 'b> Finish<'a, 'b> {
             host_data,
         }
     }
+
+    /// Encapsulate all data needed for the SNP_LAUNCH_FINISH ioctl, with a
+    /// typed ID block and its authentication information.
+    pub fn with_id(
+        id_block: &'a IdBlock,
+        id_auth: &'b IdAuth,
+        host_data: [u8; KVM_SEV_SNP_FINISH_DATA_SIZE],
+    ) -> Self {
+        let id_block = unsafe {
+            std::slice::from_raw_parts(
+                id_block as *const IdBlock as *const u8,
+                std::mem::size_of::<IdBlock>(),
+            )
+        };
+
+        let id_auth = unsafe {
+            std::slice::from_raw_parts(
+                id_auth as *const IdAuth as *const u8,
+                std::mem::size_of::<IdAuth>(),
+            )
+        };
+
+        Self::new(Some(id_block), Some(id_auth), host_data)
+    }
 }
@@ -0,0 +1,468 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The VM Save Area (VMSA) which holds the encrypted register state of a
+//! vCPU of an SEV-ES or SEV-SNP guest.
+//!
+//! The layout follows the `sev_es_save_area` structure of the Linux kernel
+//! (APM Volume 2, Appendix B, Table B-4), padded to a full page.
+
+use crate::util::*;
+
+use std::io::{Read, Result, Write};
+
+use bitflags::bitflags;
+
+/// The address of the reset vector of the bootstrap processor.
+const BSP_RESET_EIP: u32 = 0xffff_fff0;
+
+bitflags! {
+    /// Features of SEV-ES and SEV-SNP enabled for a guest.
+    #[derive(Default)]
+    pub struct SevFeatures: u64 {
+        /// The guest is an SEV-SNP guest.
+        const SNP_ACTIVE                = 1 << 0;
+
+        /// Virtual TOM is enabled.
+        const VTOM                      = 1 << 1;
+
+        /// All #VC exceptions are reflected to the hypervisor.
+        const REFLECT_VC                = 1 << 2;
+
+        /// Restricted injection is enabled.
+        const RESTRICTED_INJECTION      = 1 << 3;
+
+        /// Alternate injection is enabled.
+        const ALTERNATE_INJECTION       = 1 << 4;
+
+        /// DR0-DR3 and their address masks are swapped on world switches.
+        const DEBUG_SWAP                = 1 << 5;
+
+        /// The host is prevented from using IBS on the guest.
+        const PREVENT_HOST_IBS          = 1 << 6;
+
+        /// Branch target buffer isolation is enabled.
+        const BTB_ISOLATION             = 1 << 7;
+
+        /// VMPL supervisor shadow stacks are enabled.
+        const VMPL_SSS                  = 1 << 8;
+
+        /// Secure TSC is enabled.
+        const SECURE_TSC                = 1 << 9;
+
+        /// VMSA register protection is enabled.
+        const VMSA_REG_PROTECTION       = 1 << 14;
+    }
+}
+
+/// A segment register as saved in the VMSA.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VmcbSegment {
+    /// The segment selector.
+    pub selector: u16,
+
+    /// The segment attributes.
+    pub attrib: u16,
+
+    /// The segment limit.
+    pub limit: u32,
+
+    /// The segment base address.
+    pub base: u64,
+}
+
+impl VmcbSegment {
+    const fn new(selector: u16, attrib: u16, limit: u32, base: u64) -> Self {
+        Self {
+            selector,
+            attrib,
+            limit,
+            base,
+        }
+    }
+}
+
+/// The VM Save Area of a vCPU of an SEV-ES or SEV-SNP guest.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Vmsa {
+    /// The ES segment register.
+    pub es: VmcbSegment,
+
+    /// The CS segment register.
+    pub cs: VmcbSegment,
+
+    /// The SS segment register.
+    pub ss: VmcbSegment,
+
+    /// The DS segment register.
+    pub ds: VmcbSegment,
+
+    /// The FS segment register.
+    pub fs: VmcbSegment,
+
+    /// The GS segment register.
+    pub gs: VmcbSegment,
+
+    /// The global descriptor table register.
+    pub gdtr: VmcbSegment,
+
+    /// The local descriptor table register.
+    pub ldtr: VmcbSegment,
+
+    /// The interrupt descriptor table register.
+    pub idtr: VmcbSegment,
+
+    /// The task register.
+    pub tr: VmcbSegment,
+
+    /// The shadow stack pointer for VMPL0.
+    pub vmpl0_ssp: u64,
+
+    /// The shadow stack pointer for VMPL1.
+    pub vmpl1_ssp: u64,
+
+    /// The shadow stack pointer for VMPL2.
+    pub vmpl2_ssp: u64,
+
+    /// The shadow stack pointer for VMPL3.
+    pub vmpl3_ssp: u64,
+
+    /// The U_CET MSR.
+    pub u_cet: u64,
+
+    _reserved_0xc8: [u8; 2],
+
+    /// The VMPL the vCPU runs at.
+    pub vmpl: u8,
+
+    /// The current privilege level.
+    pub cpl: u8,
+
+    _reserved_0xcc: [u8; 4],
+
+    /// The EFER MSR.
+    pub efer: u64,
+
+    _reserved_0xd8: [u8; 104],
+
+    /// The XSS MSR.
+    pub xss: u64,
+
+    /// The CR4 control register.
+    pub cr4: u64,
+
+    /// The CR3 control register.
+    pub cr3: u64,
+
+    /// The CR0 control register.
+    pub cr0: u64,
+
+    /// The DR7 debug register.
+    pub dr7: u64,
+
+    /// The DR6 debug register.
+    pub dr6: u64,
+
+    /// The RFLAGS register.
+    pub rflags: u64,
+
+    /// The instruction pointer.
+    pub rip: u64,
+
+    /// The DR0 debug register.
+    pub dr0: u64,
+
+    /// The DR1 debug register.
+    pub dr1: u64,
+
+    /// The DR2 debug register.
+    pub dr2: u64,
+
+    /// The DR3 debug register.
+    pub dr3: u64,
+
+    /// The address mask of DR0.
+    pub dr0_addr_mask: u64,
+
+    /// The address mask of DR1.
+    pub dr1_addr_mask: u64,
+
+    /// The address mask of DR2.
+    pub dr2_addr_mask: u64,
+
+    /// The address mask of DR3.
+    pub dr3_addr_mask: u64,
+
+    _reserved_0x1c0: [u8; 24],
+
+    /// The stack pointer.
+    pub rsp: u64,
+
+    /// The S_CET MSR.
+    pub s_cet: u64,
+
+    /// The shadow stack pointer.
+    pub ssp: u64,
+
+    /// The interrupt shadow stack table address.
+    pub isst_addr: u64,
+
+    /// The RAX register.
+    pub rax: u64,
+
+    /// The STAR MSR.
+    pub star: u64,
+
+    /// The LSTAR MSR.
+    pub lstar: u64,
+
+    /// The CSTAR MSR.
+    pub cstar: u64,
+
+    /// The SFMASK MSR.
+    pub sfmask: u64,
+
+    /// The KernelGSBase MSR.
+    pub kernel_gs_base: u64,
+
+    /// The SYSENTER_CS MSR.
+    pub sysenter_cs: u64,
+
+    /// The SYSENTER_ESP MSR.
+    pub sysenter_esp: u64,
+
+    /// The SYSENTER_EIP MSR.
+    pub sysenter_eip: u64,
+
+    /// The CR2 control register.
+    pub cr2: u64,
+
+    _reserved_0x248: [u8; 32],
+
+    /// The guest PAT MSR.
+    pub g_pat: u64,
+
+    /// The DBGCTL MSR.
+    pub dbgctl: u64,
+
+    /// The BR_FROM MSR.
+    pub br_from: u64,
+
+    /// The BR_TO MSR.
+    pub br_to: u64,
+
+    /// The LASTEXCPFROM MSR.
+    pub last_excp_from: u64,
+
+    /// The LASTEXCPTO MSR.
+    pub last_excp_to: u64,
+
+    _reserved_0x298: [u8; 80],
+
+    /// The PKRU register.
+    pub pkru: u32,
+
+    /// The TSC_AUX MSR.
+    pub tsc_aux: u32,
+
+    _reserved_0x2f0: [u8; 24],
+
+    /// The RCX register.
+    pub rcx: u64,
+
+    /// The RDX register. At reset it holds the processor signature.
+    pub rdx: u64,
+
+    /// The RBX register.
+    pub rbx: u64,
+
+    _reserved_0x320: u64,
+
+    /// The RBP register.
+    pub rbp: u64,
+
+    /// The RSI register.
+    pub rsi: u64,
+
+    /// The RDI register.
+    pub rdi: u64,
+
+    /// The R8 register.
+    pub r8: u64,
+
+    /// The R9 register.
+    pub r9: u64,
+
+    /// The R10 register.
+    pub r10: u64,
+
+    /// The R11 register.
+    pub r11: u64,
+
+    /// The R12 register.
+    pub r12: u64,
+
+    /// The R13 register.
+    pub r13: u64,
+
+    /// The R14 register.
+    pub r14: u64,
+
+    /// The R15 register.
+    pub r15: u64,
+
+    _reserved_0x380: [u8; 16],
+
+    /// The EXITINFO1 field of the last #VMEXIT.
+    pub guest_exit_info_1: u64,
+
+    /// The EXITINFO2 field of the last #VMEXIT.
+    pub guest_exit_info_2: u64,
+
+    /// The EXITINTINFO field of the last #VMEXIT.
+    pub guest_exit_int_info: u64,
+
+    /// The next sequential instruction pointer.
+    pub guest_nrip: u64,
+
+    /// The SEV features enabled for the guest.
+    pub sev_features: SevFeatures,
+
+    /// The virtual interrupt control.
+    pub vintr_ctrl: u64,
+
+    /// The EXITCODE of the last #VMEXIT.
+    pub guest_exit_code: u64,
+
+    /// The virtual top of memory.
+    pub virtual_tom: u64,
+
+    /// The TLB ID.
+    pub tlb_id: u64,
+
+    /// The physical CPU ID.
+    pub pcpu_id: u64,
+
+    /// The event injection field.
+    pub event_inj: u64,
+
+    /// The XCR0 register.
+    pub xcr0: u64,
+
+    _reserved_0x3f0: [u8; 16],
+
+    /// The x87 data pointer.
+    pub x87_dp: u64,
+
+    /// The MXCSR register.
+    pub mxcsr: u32,
+
+    /// The x87 tag word.
+    pub x87_ftw: u16,
+
+    /// The x87 status word.
+    pub x87_fsw: u16,
+
+    /// The x87 control word.
+    pub x87_fcw: u16,
+
+    /// The x87 last opcode.
+    pub x87_fop: u16,
+
+    /// The x87 data segment.
+    pub x87_ds: u16,
+
+    /// The x87 code segment.
+    pub x87_cs: u16,
+
+    /// The x87 instruction pointer.
+    pub x87_rip: u64,
+
+    /// The x87 registers.
+    pub fpreg_x87: [u8; 80],
+
+    /// The XMM registers.
+    pub fpreg_xmm: [u8; 256],
+
+    /// The upper halves of the YMM registers.
+    pub fpreg_ymm: [u8; 256],
+
+    _reserved_0x670: [u8; 2448],
+}
+
+impl Default for Vmsa {
+    fn default() -> Self {
+        // All fields are integers (or arrays of them) for which zero is valid.
+        unsafe { std::mem::zeroed() }
+    }
+}
+
+impl Vmsa {
+    /// The architectural reset state of a vCPU starting at `eip`.
+    ///
+    /// The code segment base holds the upper 16 bits of `eip` and the
+    /// instruction pointer the lower 16 bits, as for the reset vector.
+    /// RDX holds `vcpu_sig`, the processor signature of the vCPU (the value
+    /// of EAX returned by its CPUID function 1), which must match the one
+    /// used by the VMM for the launch digest to match.
+    pub fn new(eip: u32, vcpu_sig: u32, sev_features: SevFeatures) -> Self {
+        let data = VmcbSegment::new(0, 0x93, 0xffff, 0);
+
+        Self {
+            es: data,
+            cs: VmcbSegment::new(0xf000, 0x9b, 0xffff, u64::from(eip & 0xffff_0000)),
+            ss: data,
+            ds: data,
+            fs: data,
+            gs: data,
+            gdtr: VmcbSegment::new(0, 0, 0xffff, 0),
+            ldtr: VmcbSegment::new(0, 0x82, 0xffff, 0),
+            idtr: VmcbSegment::new(0, 0, 0xffff, 0),
+            tr: VmcbSegment::new(0, 0x83, 0xffff, 0),
+            efer: 0x1000,
+            cr4: 0x40,
+            cr0: 0x10,
+            dr7: 0x400,
+            dr6: 0xffff_0ff0,
+            rflags: 0x2,
+            rip: u64::from(eip & 0xffff),
+            rdx: u64::from(vcpu_sig),
+            g_pat: 0x0007_0406_0007_0406,
+            sev_features,
+            xcr0: 0x1,
+            mxcsr: 0x1f80,
+            x87_fcw: 0x37f,
+            ..Default::default()
+        }
+    }
+
+    /// The reset state of the bootstrap processor, which starts at the
+    /// reset vector.
+    pub fn bsp(vcpu_sig: u32, sev_features: SevFeatures) -> Self {
+        Self::new(BSP_RESET_EIP, vcpu_sig, sev_features)
+    }
+
+    /// The reset state of an application processor, which starts at the
+    /// `eip` provided by the firmware (see
+    /// [`Ovmf::sev_es_reset_eip`](super::ovmf::Ovmf::sev_es_reset_eip)).
+    pub fn ap(eip: u32, vcpu_sig: u32, sev_features: SevFeatures) -> Self {
+        Self::new(eip, vcpu_sig, sev_features)
+    }
+}
+
+impl codicon::Decoder<()> for Vmsa {
+    type Error = std::io::Error;
+
+    fn decode(mut reader: impl Read, _: ()) -> Result<Self> {
+        reader.load()
+    }
+}
+
+impl codicon::Encoder<()> for Vmsa {
+    type Error = std::io::Error;
+
+    fn encode(&self, mut writer: impl Write, _: ()) -> Result<()> {
+        writer.save(self)
+    }
+}
@@ -53,7 +53,6 @@ use certs::{builtin, ca};
 
 use serde::{Deserialize, Serialize};
 
-#[cfg(feature = "openssl")]
 use std::convert::TryFrom;
 use std::io::{Read, Write};
 
@@ -156,6 +155,8 @@ impl codicon::Encoder<()> for Build {
 /// }
 /// # }
 /// ```
+#[deprecated(note = "use `Product`, which also covers Genoa and Turin")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Generation {
     /// First generation EPYC (SEV).
     Naples,
@@ -167,6 +168,7 @@ pub enum Generation {
     Milan,
 }
 
+#[allow(deprecated)]
 impl From<Generation> for ca::Chain {
     fn from(generation: Generation) -> ca::Chain {
         use codicon::Decoder;
@@ -185,6 +187,7 @@ impl From<Generation> for ca::Chain {
 }
 
 #[cfg(feature = "openssl")]
+#[allow(deprecated)]
 impl TryFrom<&sev::Chain> for Generation {
     type Error = ();
 
@@ -207,6 +210,238 @@ impl TryFrom<&sev::Chain> for Generation {
     }
 }
 
+/// A representation for EPYC generational product lines.
+///
+/// The product can be detected from the CPUID family and model of the
+/// processor with [`Product::identify`], from a SEV certificate chain, or
+/// from the issuer of a SEV-SNP VCEK or VLEK certificate. The builtin SEV
+/// certificates of a product are available through the [TryFrom](
+/// https://doc.rust-lang.org/std/convert/trait.TryFrom.html) trait.
+///
+/// ## Example
+///
+/// ```no_run
+/// # #[cfg(feature = "openssl")]
+/// # {
+///
+/// // NOTE: Detecting the product from a certificate chain requires the
+/// // `sev` crate to have the `openssl` feature enabled.
+///
+/// use std::convert::TryFrom;
+/// use sev::firmware::Firmware;
+/// use sev::Product;
+///
+/// let mut firmware = Firmware::open().expect("failed to open /dev/sev");
+///
+/// let chain = firmware.pdh_cert_export()
+///     .expect("unable to export SEV certificates");
+///
+/// let product = Product::try_from(&chain).expect("not a SEV/ES chain");
+/// println!("{}", product);
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Product {
+    /// First generation EPYC (SEV).
+    Naples,
+
+    /// Second generation EPYC (SEV, SEV-ES).
+    Rome,
+
+    /// Third generation EPYC (SEV, SEV-ES, SEV-SNP).
+    Milan,
+
+    /// Fourth generation EPYC (SEV, SEV-ES, SEV-SNP).
+    Genoa,
+
+    /// Fifth generation EPYC (SEV, SEV-ES, SEV-SNP).
+    Turin,
+}
+
+impl Product {
+    /// Identify the product from a CPU family and model, as reported by
+    /// CPUID function 1.
+    pub fn from_family_model(family: u32, model: u32) -> Option<Self> {
+        Some(match (family, model) {
+            (0x17, 0x00..=0x0F) => Product::Naples,
+            (0x17, 0x30..=0x3F) => Product::Rome,
+            (0x19, 0x00..=0x0F) => Product::Milan,
+            (0x19, 0x10..=0x1F) | (0x19, 0xA0..=0xAF) => Product::Genoa,
+            (0x1A, 0x00..=0x1F) => Product::Turin,
+            _ => return None,
+        })
+    }
+
+    /// Identify the product from the processor signature, i.e. the value
+    /// of the EAX register returned by CPUID function 1.
+    pub fn from_cpuid_signature(eax: u32) -> Option<Self> {
+        let base_family = (eax >> 8) & 0xF;
+        let base_model = (eax >> 4) & 0xF;
+
+        let (family, model) = if base_family == 0xF {
+            let ext_family = (eax >> 20) & 0xFF;
+            let ext_model = (eax >> 16) & 0xF;
+            (base_family + ext_family, (ext_model << 4) | base_model)
+        } else {
+            (base_family, base_model)
+        };
+
+        Self::from_family_model(family, model)
+    }
+
+    /// Identify the product of the processor this code is running on.
+    ///
+    /// The processor signature is read through the Linux CPUID driver
+    /// (`/dev/cpu/0/cpuid`), which may require elevated privileges.
+    #[cfg(target_os = "linux")]
+    pub fn identify() -> std::io::Result<Self> {
+        Self::identify_with(|function| {
+            use std::os::unix::fs::FileExt;
+
+            let file = std::fs::File::open("/dev/cpu/0/cpuid")?;
+            let mut regs = [0u8; 16];
+            file.read_exact_at(&mut regs, function.into())?;
+            Ok(u32::from_le_bytes([regs[0], regs[1], regs[2], regs[3]]))
+        })
+    }
+
+    /// Identify the product of a processor, using `cpuid` to obtain the
+    /// value of the EAX register returned by the given CPUID function.
+    pub fn identify_with(cpuid: impl FnOnce(u32) -> std::io::Result<u32>) -> std::io::Result<Self> {
+        let eax = cpuid(1)?;
+        Self::from_cpuid_signature(eax).ok_or_else(|| std::io::ErrorKind::InvalidData.into())
+    }
+
+    /// The name AMD uses for this product, e.g. in the URLs of its Key
+    /// Distribution Service (KDS).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Product::Naples => "Naples",
+            Product::Rome => "Rome",
+            Product::Milan => "Milan",
+            Product::Genoa => "Genoa",
+            Product::Turin => "Turin",
+        }
+    }
+}
+
+impl std::fmt::Display for Product {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for Product {
+    type Err = UnknownProduct;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "Naples" => Product::Naples,
+            "Rome" => Product::Rome,
+            "Milan" => Product::Milan,
+            "Genoa" => Product::Genoa,
+            "Turin" => Product::Turin,
+            _ => return Err(UnknownProduct(name.to_string())),
+        })
+    }
+}
+
+/// The error returned when parsing the name of an unknown [`Product`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownProduct(pub String);
+
+impl std::fmt::Display for UnknownProduct {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "unknown EPYC product: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownProduct {}
+
+#[allow(deprecated)]
+impl From<Generation> for Product {
+    fn from(generation: Generation) -> Self {
+        match generation {
+            Generation::Naples => Product::Naples,
+            Generation::Rome => Product::Rome,
+            Generation::Milan => Product::Milan,
+        }
+    }
+}
+
+/// The builtin SEV certificates of a product.
+///
+/// Fails with `NotFound` for the products for which no builtin SEV
+/// certificates are shipped yet (Genoa and Turin).
+impl TryFrom<Product> for ca::Chain {
+    type Error = std::io::Error;
+
+    fn try_from(product: Product) -> std::io::Result<Self> {
+        use codicon::Decoder;
+
+        let (ark, ask) = match product {
+            Product::Naples => (builtin::naples::ARK, builtin::naples::ASK),
+            Product::Rome => (builtin::rome::ARK, builtin::rome::ASK),
+            Product::Milan => (builtin::milan::ARK, builtin::milan::ASK),
+            Product::Genoa | Product::Turin => return Err(std::io::ErrorKind::NotFound.into()),
+        };
+
+        Ok(ca::Chain {
+            ask: ca::Certificate::decode(&mut &*ask, ())?,
+            ark: ca::Certificate::decode(&mut &*ark, ())?,
+        })
+    }
+}
+
+/// Identifies the product whose builtin ASK signed the CEK of a SEV
+/// certificate chain.
+#[cfg(feature = "openssl")]
+impl TryFrom<&sev::Chain> for Product {
+    type Error = ();
+
+    fn try_from(schain: &sev::Chain) -> Result<Self, Self::Error> {
+        use crate::certs::Verifiable;
+
+        for product in [Product::Naples, Product::Rome, Product::Milan].iter() {
+            let chain = ca::Chain::try_from(*product).map_err(|_| ())?;
+            if (&chain.ask, &schain.cek).verify().is_ok() {
+                return Ok(*product);
+            }
+        }
+
+        Err(())
+    }
+}
+
+/// Identifies the product from the issuer of a SEV-SNP VCEK or VLEK,
+/// which is the ASK (e.g. `SEV-Milan`) or ASVK (e.g. `SEV-VLEK-Milan`) of
+/// that product.
+///
+/// The error holds the common name of the issuer, if any.
+#[cfg(feature = "openssl")]
+impl TryFrom<&certs::snp::Certificate> for Product {
+    type Error = UnknownProduct;
+
+    fn try_from(cert: &certs::snp::Certificate) -> Result<Self, Self::Error> {
+        let issuer = cert
+            .as_ref()
+            .issuer_name()
+            .entries_by_nid(openssl::nid::Nid::COMMONNAME)
+            .next()
+            .map(|cn| String::from_utf8_lossy(cn.data().as_slice()).into_owned())
+            .unwrap_or_default();
+
+        let name = issuer
+            .strip_prefix("SEV-")
+            .map(|name| name.strip_prefix("VLEK-").unwrap_or(name));
+
+        match name.map(str::parse) {
+            Some(Ok(product)) => Ok(product),
+            _ => Err(UnknownProduct(issuer)),
+        }
+    }
+}
+
 /// A description of the SEV-SNP platform's build information.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -19,6 +19,10 @@ pub struct Measuring(hash::Hasher);
 /// Denotes an agreeable measurement with the AMD SP.
 pub struct Verified(launch::sev::Measurement);
 
+/// Indicates the Session is used for transporting a guest to (or from)
+/// another platform.
+pub struct Transport;
+
 /// Describes a secure channel with the AMD SP.
 ///
 /// This is required for facilitating an SEV launch and attestation.
@@ -34,12 +38,6 @@ pub struct Session<T> {
     data: T,
 }
 
-impl launch::sev::Policy {
-    fn bytes(self) -> [u8; 4] {
-        unsafe { std::mem::transmute(self) }
-    }
-}
-
 impl std::convert::TryFrom<launch::sev::Policy> for Session<Initialized> {
     type Error = std::io::Error;
 
@@ -53,7 +51,7 @@ impl std::convert::TryFrom<launch::sev::Policy> for Session<Initialized> {
     }
 }
 
-impl Session<Initialized> {
+impl<T> Session<T> {
     fn session(&self, nonce: [u8; 16], iv: [u8; 16], z: key::Key) -> Result<launch::sev::Session> {
         let master = z.derive(16, &nonce, "sev-master-secret")?;
         let kek = master.derive(16, &[], "sev-kek")?;
@@ -68,8 +66,8 @@ impl Session<Initialized> {
 
         let mut wrap = [0u8; 32];
         let mut off = 0;
-        off += crypter.update(&*self.tek, &mut wrap[off..])?;
-        off += crypter.update(&*self.tik, &mut wrap[off..])?;
+        off += crypter.update(&self.tek, &mut wrap[off..])?;
+        off += crypter.update(&self.tik, &mut wrap[off..])?;
         off += crypter.finalize(&mut wrap[off..])?;
         assert_eq!(off, wrap.len());
 
@@ -85,11 +83,9 @@ impl Session<Initialized> {
         })
     }
 
-    /// Produces data needed to initiate the SEV launch sequence.
-    pub fn start(&self, chain: certs::Chain) -> Result<launch::sev::Start> {
-        use certs::*;
-
-        let pdh = chain.verify()?;
+    /// Wraps the TEK and TIK for the given PDH with a freshly generated
+    /// Diffie-Hellman certificate.
+    fn wrap(&self, pdh: &certs::sev::Certificate) -> Result<launch::sev::Start> {
         let (crt, prv) = sev::Certificate::generate(sev::Usage::PDH)?;
 
         let z = key::Key::new(prv.derive(pdh)?);
@@ -105,22 +101,39 @@ impl Session<Initialized> {
         })
     }
 
+    /// Calculates the MAC of a transport packet.
+    fn packet_mac(
+        &self,
+        flags: launch::sev::HeaderFlags,
+        iv: &[u8; 16],
+        ciphertext: &[u8],
+    ) -> Result<[u8; 32]> {
+        let key = pkey::PKey::hmac(&self.tik)?;
+        let mut sig = sign::Signer::new(hash::MessageDigest::sha256(), &key)?;
+
+        sig.update(&flags.bits().to_le_bytes())?;
+        sig.update(iv)?;
+        sig.update(ciphertext)?;
+
+        let mut mac = [0u8; 32];
+        sig.sign(&mut mac)?;
+        Ok(mac)
+    }
+}
+
+impl Session<Initialized> {
+    /// Produces data needed to initiate the SEV launch sequence.
+    pub fn start(&self, chain: certs::Chain) -> Result<launch::sev::Start> {
+        use certs::*;
+
+        let pdh = chain.verify()?;
+        self.wrap(pdh)
+    }
+
     /// Like the above start function, yet takes PDH as input instead of deriving it from a
     /// certificate chain.
     pub fn start_pdh(&self, pdh: certs::sev::Certificate) -> Result<launch::sev::Start> {
-        let (crt, prv) = sev::Certificate::generate(sev::Usage::PDH)?;
-
-        let z = key::Key::new(prv.derive(&pdh)?);
-        let mut nonce = [0u8; 16];
-        let mut iv = [0u8; 16];
-        rand::rand_bytes(&mut nonce)?;
-        rand::rand_bytes(&mut iv)?;
-
-        Ok(launch::sev::Start {
-            policy: self.policy,
-            cert: crt,
-            session: self.session(nonce, iv, z)?,
-        })
+        self.wrap(&pdh)
     }
 
     /// Transitions to a measuring state.
@@ -143,16 +156,8 @@ impl Session<Initialized> {
         build: Build,
         msr: launch::sev::Measurement,
     ) -> Result<Session<Verified>> {
-        let key = pkey::PKey::hmac(&*self.tik)?;
-        let mut sig = sign::Signer::new(hash::MessageDigest::sha256(), &key)?;
-
-        sig.update(&[0x04u8])?;
-        sig.update(&[build.version.major, build.version.minor, build.build])?;
-        sig.update(&self.policy.bytes())?;
-        sig.update(digest)?;
-        sig.update(&msr.mnonce)?;
-
-        if sig.sign_to_vec()? != msr.measure {
+        let measure = launch::sev::measure(digest, self.policy, build, &self.tik, msr.mnonce)?;
+        if measure != msr.measure {
             return Err(ErrorKind::InvalidInput.into());
         }
 
@@ -177,6 +182,17 @@ impl Session<Initialized> {
             data: Verified(msr),
         })
     }
+
+    /// Transitions to a transport state, for migrating a guest with this
+    /// session's policy between platforms.
+    pub fn transport(self) -> Session<Transport> {
+        Session {
+            policy: self.policy,
+            tek: self.tek,
+            tik: self.tik,
+            data: Transport,
+        }
+    }
 }
 
 impl Session<Measuring> {
@@ -216,13 +232,13 @@ impl Session<Verified> {
         let mut iv = [0u8; 16];
         rand::rand_bytes(&mut iv)?;
 
-        let ciphertext = symm::encrypt(symm::Cipher::aes_128_ctr(), &*self.tek, Some(&iv), data)?;
+        let ciphertext = symm::encrypt(symm::Cipher::aes_128_ctr(), &self.tek, Some(&iv), data)?;
 
-        let key = pkey::PKey::hmac(&*self.tik)?;
+        let key = pkey::PKey::hmac(&self.tik)?;
         let mut sig = sign::Signer::new(hash::MessageDigest::sha256(), &key)?;
 
         sig.update(&[0x01u8])?;
-        sig.update(&unsafe { std::mem::transmute::<_, [u8; 4]>(flags) })?;
+        sig.update(&flags.bits().to_le_bytes())?;
         sig.update(&iv)?;
         sig.update(&(data.len() as u32).to_le_bytes())?;
         sig.update(&(ciphertext.len() as u32).to_le_bytes())?;
@@ -239,6 +255,68 @@ impl Session<Verified> {
     }
 }
 
+impl Session<Transport> {
+    /// Produces the session blob and the source PDH certificate that the
+    /// target platform needs for RECEIVE_START.
+    ///
+    /// The TEK and TIK are wrapped for the PDH of the target platform
+    /// after verifying its certificate chain.
+    pub fn start(&self, target: certs::Chain) -> Result<launch::sev::Start> {
+        use certs::*;
+
+        let pdh = target.verify()?;
+        self.wrap(pdh)
+    }
+
+    /// Encrypts guest data into a packet for the target platform.
+    #[cfg(target_os = "linux")]
+    pub fn packet(
+        &self,
+        flags: launch::sev::HeaderFlags,
+        data: &[u8],
+    ) -> Result<launch::migrate::Packet> {
+        let mut iv = [0u8; 16];
+        rand::rand_bytes(&mut iv)?;
+
+        let ciphertext = symm::encrypt(symm::Cipher::aes_128_ctr(), &self.tek, Some(&iv), data)?;
+        let mac = self.packet_mac(flags, &iv, &ciphertext)?;
+
+        Ok(launch::migrate::Packet {
+            header: launch::sev::Header { flags, iv, mac },
+            ciphertext,
+        })
+    }
+
+    /// Verifies the MAC of a packet received from the source platform.
+    #[cfg(target_os = "linux")]
+    pub fn verify(&self, packet: &launch::migrate::Packet) -> Result<()> {
+        let header = &packet.header;
+        let mac = self.packet_mac(header.flags, &header.iv, &packet.ciphertext)?;
+
+        if !memcmp::eq(&mac, &header.mac) {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        Ok(())
+    }
+
+    /// Verifies a packet received from the source platform and decrypts
+    /// its contents.
+    #[cfg(target_os = "linux")]
+    pub fn open(&self, packet: &launch::migrate::Packet) -> Result<Vec<u8>> {
+        self.verify(packet)?;
+
+        let data = symm::decrypt(
+            symm::Cipher::aes_128_ctr(),
+            &self.tek,
+            Some(&packet.header.iv),
+            &packet.ciphertext,
+        )?;
+
+        Ok(data)
+    }
+}
+
 #[cfg(test)]
 mod initialized {
     use super::*;
@@ -337,3 +415,37 @@ mod initialized {
         session.verify(&digest, build, measurement).unwrap();
     }
 }
+
+#[cfg(test)]
+mod transport {
+    use super::*;
+    use crate::{launch, session::Session};
+
+    #[test]
+    fn packet_mac() {
+        let session = Session {
+            policy: launch::sev::Policy::default(),
+            tek: key::Key::new(vec![0u8; 16]),
+            tik: key::Key::new((0..16).collect()),
+            data: Transport,
+        };
+
+        // HMAC-SHA256 over flags || iv || ciphertext.
+        let mac = session
+            .packet_mac(
+                launch::sev::HeaderFlags::COMPRESSED,
+                &[0x11u8; 16],
+                &[0xa5u8; 32],
+            )
+            .unwrap();
+
+        assert_eq!(
+            mac,
+            [
+                0x5c, 0x61, 0x40, 0x31, 0xc0, 0xa9, 0xda, 0x1c, 0x79, 0x8f, 0x31, 0xbe, 0x2b, 0xa0,
+                0x0b, 0x64, 0xe9, 0x79, 0x10, 0x90, 0x33, 0xf1, 0x58, 0x6c, 0x80, 0xea, 0x47, 0x31,
+                0x9f, 0xd9, 0x39, 0xbe,
+            ]
+        );
+    }
+}
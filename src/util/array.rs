@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Serde support for byte arrays larger than those supported by serde itself.
+//!
+//! Use with `#[serde(with = "crate::util::array")]`.
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+use serde_bytes::ByteBuf;
+
+pub fn serialize<S: Serializer, const N: usize>(
+    value: &[u8; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(value)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    let bytes = ByteBuf::deserialize(deserializer)?;
+    if bytes.len() != N {
+        return Err(de::Error::invalid_length(
+            bytes.len(),
+            &"a fixed-size byte array",
+        ));
+    }
+
+    let mut value = [0u8; N];
+    value.copy_from_slice(&bytes);
+    Ok(value)
+}
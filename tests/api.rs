@@ -155,3 +155,50 @@ fn snp_platform_status() {
         status.state
     );
 }
+
+#[test]
+fn snp_config_layout() {
+    use sev::firmware::{SnpConfig, SnpConfigMask, TcbVersion};
+
+    assert_eq!(std::mem::size_of::<SnpConfig>(), 64);
+
+    let config = SnpConfig::new(TcbVersion::default(), SnpConfigMask::MASK_CHIP_ID);
+    assert_eq!(config.mask.bits(), 1);
+    assert_eq!(SnpConfig::default().mask, SnpConfigMask::empty());
+}
+
+#[cfg_attr(not(all(has_sev, feature = "dangerous_hw_tests")), ignore)]
+#[ignore]
+#[test]
+#[serial]
+fn snp_set_config() {
+    use sev::firmware::{SnpConfig, SnpConfigMask};
+
+    let mut fw = Firmware::open().unwrap();
+    let status = fw.snp_platform_status().unwrap();
+
+    let config = SnpConfig::new(status.tcb.platform_version, SnpConfigMask::empty());
+    fw.snp_set_config(&config).unwrap();
+
+    let status = fw.snp_platform_status().unwrap();
+    assert_eq!(status.tcb.reported_version, config.reported_tcb);
+}
+
+#[cfg_attr(not(all(has_sev, feature = "dangerous_hw_tests")), ignore)]
+#[ignore]
+#[test]
+#[serial]
+fn snp_commit() {
+    let mut fw = Firmware::open().unwrap();
+    fw.snp_commit().unwrap();
+}
+
+#[test]
+fn wrapped_vlek_hashstick() {
+    use sev::firmware::WrappedVlekHashstick;
+    use std::convert::TryFrom;
+
+    let hashstick = WrappedVlekHashstick::try_from(&[0xAA; 432][..]).unwrap();
+    assert_eq!(hashstick.data, [0xAA; 432]);
+    assert!(WrappedVlekHashstick::try_from(&[0xAA; 431][..]).is_err());
+}
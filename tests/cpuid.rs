@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use sev::launch::cpuid::*;
+
+use codicon::{Decoder, Encoder};
+
+fn source(leaf: u32, subleaf: u32) -> Registers {
+    match (leaf, subleaf) {
+        (0x0, 0) => Registers {
+            eax: 0xd,
+            ebx: 0x6874_7541,
+            ecx: 0x444d_4163,
+            edx: 0x6974_6e65,
+        },
+        (0x1, 0) => Registers {
+            eax: 0x00a0_0f11,
+            ecx: 0x7ef8_320b,
+            edx: 0x178b_fbff,
+            ..Default::default()
+        },
+        (0x7, 0) => Registers {
+            ebx: 0x219c_97a9,
+            ..Default::default()
+        },
+        (0xd, 0) => Registers {
+            eax: 0x7,
+            ebx: 0x340,
+            ecx: 0x340,
+            ..Default::default()
+        },
+        (0xd, 1) => Registers {
+            eax: 0xf,
+            ..Default::default()
+        },
+        (0xd, 2) => Registers {
+            eax: 0x100,
+            ebx: 0x240,
+            ..Default::default()
+        },
+        (0x8000_0000, 0) => Registers {
+            eax: 0x8000_0001,
+            ..Default::default()
+        },
+        _ => Registers::default(),
+    }
+}
+
+#[test]
+fn layout() {
+    assert_eq!(std::mem::size_of::<CpuidEntry>(), 48);
+    assert_eq!(std::mem::size_of::<CpuidPage>(), 4096);
+}
+
+#[test]
+fn from_source() {
+    let page = CpuidPage::from_source(&mut source).unwrap();
+
+    let inputs: Vec<_> = page
+        .entries()
+        .iter()
+        .map(|e| (e.eax_in, e.ecx_in))
+        .collect();
+    assert_eq!(
+        inputs,
+        vec![
+            (0x0, 0),
+            (0x1, 0),
+            (0x7, 0),
+            (0xd, 0),
+            (0xd, 1),
+            (0xd, 2),
+            (0x8000_0000, 0)
+        ]
+    );
+
+    let xsave = page.get(0xd, 0).unwrap();
+    assert_eq!(xsave.xcr0_in, 1);
+    assert_eq!(xsave.ebx, 0x240);
+    assert_eq!(page.get(0xd, 2).unwrap().xcr0_in, 0);
+}
+
+#[test]
+fn push() {
+    let mut page = CpuidPage::new();
+    for leaf in 0..MAX_ENTRIES as u32 {
+        page.push(CpuidEntry::new(leaf, 0, Registers::default()))
+            .unwrap();
+    }
+
+    assert!(page
+        .push(CpuidEntry::new(0x40, 0, Registers::default()))
+        .is_err());
+    assert_eq!(page.entries().len(), MAX_ENTRIES);
+}
+
+#[test]
+fn codec() {
+    let page = CpuidPage::from_source(&mut source).unwrap();
+
+    let mut bytes = vec![];
+    page.encode(&mut bytes, ()).unwrap();
+    assert_eq!(bytes.len(), 4096);
+    assert_eq!(&bytes[..4], &7u32.to_le_bytes());
+    assert_eq!(&bytes[0x10..0x14], &0u32.to_le_bytes());
+    assert_eq!(&bytes[0x40..0x44], &1u32.to_le_bytes());
+
+    assert_eq!(CpuidPage::decode(&bytes[..], ()).unwrap(), page);
+
+    bytes[0] = 65;
+    assert!(CpuidPage::decode(&bytes[..], ()).is_err());
+}
+
+#[test]
+fn diff() {
+    let submitted = CpuidPage::from_source(&mut source).unwrap();
+    assert!(submitted.diff(&submitted).is_empty());
+
+    // The firmware clears the unsupported features it was offered.
+    let mut bytes = vec![];
+    submitted.encode(&mut bytes, ()).unwrap();
+    let ebx = 0x10 + 2 * 48 + 0x1c;
+    bytes[ebx..ebx + 4].copy_from_slice(&0x219c_9729u32.to_le_bytes());
+    let returned = CpuidPage::decode(&bytes[..], ()).unwrap();
+
+    let mismatches = submitted.diff(&returned);
+    assert_eq!(
+        mismatches,
+        vec![Mismatch {
+            leaf: 0x7,
+            subleaf: 0,
+            register: Register::Ebx,
+            submitted: 0x219c_97a9,
+            corrected: 0x219c_9729,
+        }]
+    );
+    assert_eq!(
+        mismatches[0].to_string(),
+        "CPUID 0x00000007.0x0 EBX: submitted 0x219c97a9, firmware returned 0x219c9729"
+    );
+}
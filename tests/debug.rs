@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use sev::launch::sev::*;
+
+use std::fs::File;
+
+#[test]
+fn not_a_guest() {
+    let mut vm = File::open("/dev/null").unwrap();
+    let mut sev = File::open("/dev/null").unwrap();
+
+    // The policy cannot be queried, so no debugger is created.
+    assert!(GuestMemoryDebugger::new(&mut vm, &mut sev).is_err());
+}
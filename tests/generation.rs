@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use sev::certs::ca;
+use sev::{Product, UnknownProduct};
+
+use std::convert::TryFrom;
+
+#[test]
+fn from_cpuid_signature() {
+    let cases = [
+        (0x00800F12, Product::Naples), // EPYC 7601
+        (0x00830F10, Product::Rome),   // EPYC 7742
+        (0x00A00F11, Product::Milan),  // EPYC 7763
+        (0x00A10F11, Product::Genoa),  // EPYC 9654
+        (0x00AA0F01, Product::Genoa),  // EPYC 9754
+        (0x00B00F21, Product::Turin),  // EPYC 9755
+    ];
+
+    for (eax, product) in cases.iter() {
+        assert_eq!(Product::from_cpuid_signature(*eax), Some(*product));
+    }
+
+    // Zen 2 client parts share the family with Rome.
+    assert_eq!(Product::from_cpuid_signature(0x00870F10), None);
+    // Intel.
+    assert_eq!(Product::from_cpuid_signature(0x000906EA), None);
+}
+
+#[test]
+fn identify_with() {
+    let product = Product::identify_with(|function| {
+        assert_eq!(function, 1);
+        Ok(0x00A10F11)
+    });
+    assert_eq!(product.unwrap(), Product::Genoa);
+
+    assert!(Product::identify_with(|_| Ok(0x000906EA)).is_err());
+}
+
+#[test]
+fn name() {
+    for product in [
+        Product::Naples,
+        Product::Rome,
+        Product::Milan,
+        Product::Genoa,
+        Product::Turin,
+    ]
+    .iter()
+    {
+        let name = product.to_string();
+        assert_eq!(name.parse::<Product>(), Ok(*product));
+    }
+
+    assert_eq!("Genoa".parse(), Ok(Product::Genoa));
+    assert_eq!(
+        "genoa".parse::<Product>(),
+        Err(UnknownProduct("genoa".to_string()))
+    );
+}
+
+#[test]
+#[allow(deprecated)]
+fn generation() {
+    use sev::Generation;
+
+    assert_eq!(Product::from(Generation::Naples), Product::Naples);
+    assert_eq!(Product::from(Generation::Rome), Product::Rome);
+    assert_eq!(Product::from(Generation::Milan), Product::Milan);
+
+    let _: ca::Chain = Generation::Milan.into();
+}
+
+#[test]
+fn builtin_chain() {
+    for product in [Product::Naples, Product::Rome, Product::Milan].iter() {
+        assert!(ca::Chain::try_from(*product).is_ok());
+    }
+
+    for product in [Product::Genoa, Product::Turin].iter() {
+        let err = ca::Chain::try_from(*product).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+}
+
+#[cfg(feature = "openssl")]
+#[test]
+fn from_sev_chain() {
+    use codicon::Decoder;
+    use sev::certs::sev;
+
+    let chain = sev::Chain {
+        cek: sev::Certificate::decode(&mut &include_bytes!("naples/cek.cert")[..], ()).unwrap(),
+        oca: sev::Certificate::decode(&mut &include_bytes!("naples/oca.cert")[..], ()).unwrap(),
+        pek: sev::Certificate::decode(&mut &include_bytes!("naples/pek.cert")[..], ()).unwrap(),
+        pdh: sev::Certificate::decode(&mut &include_bytes!("naples/pdh.cert")[..], ()).unwrap(),
+    };
+
+    assert_eq!(Product::try_from(&chain), Ok(Product::Naples));
+}
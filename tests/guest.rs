@@ -0,0 +1,275 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use sev::firmware::guest::*;
+use sev::firmware::{CertTable, CertTableEntry, CertType, Error, Indeterminate, TcbVersion};
+
+use codicon::{Decoder, Encoder};
+
+#[test]
+fn fake_device() {
+    let path = std::env::temp_dir().join(format!("sev-guest-{}", std::process::id()));
+    std::fs::write(&path, b"").unwrap();
+
+    let mut fw = GuestFirmware::open_path(&path).unwrap();
+    let report = fw.get_report([0u8; 64], 0);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(
+        report,
+        Err(Indeterminate::Known(Error::IoError(_)))
+    ));
+}
+
+#[test]
+fn report_codec() {
+    let mut bytes = vec![0u8; 1184];
+    bytes[0x00] = 2; // version
+    bytes[0x04] = 7; // guest_svn
+    bytes[0x08..0x10].copy_from_slice(&0x30000u64.to_le_bytes()); // policy
+    bytes[0x30] = 1; // vmpl
+    bytes[0x34] = 1; // sig_algo
+    bytes[0x38] = 3; // current_tcb.bootloader
+    bytes[0x3F] = 0x73; // current_tcb.microcode
+    bytes[0x40] = 0b11; // plat_info
+    bytes[0x50..0x90].copy_from_slice(&[0xAA; 64]); // report_data
+    bytes[0x90..0xC0].copy_from_slice(&[0xBB; 48]); // measurement
+    bytes[0x186] = 8; // reported_tcb.snp
+    bytes[0x1A0..0x1E0].copy_from_slice(&[0xCC; 64]); // chip_id
+    bytes[0x1E8..0x1EB].copy_from_slice(&[5, 52, 1]); // current build/minor/major
+    bytes[0x2A0] = 0x11; // signature.r
+    bytes[0x2E8] = 0x22; // signature.s
+
+    let report = AttestationReport::decode(&bytes[..], ()).unwrap();
+    assert_eq!(report.version, 2);
+    assert_eq!(report.guest_svn, 7);
+    assert_eq!(report.policy, 0x30000);
+    assert_eq!(
+        report.guest_policy().unwrap().flags,
+        sev::launch::snp::PolicyFlags::SMT
+    );
+    assert_eq!(report.vmpl, 1);
+    assert_eq!(report.sig_algo, 1);
+    assert_eq!(report.current_tcb.bootloader, 3);
+    assert_eq!(report.current_tcb.microcode, 0x73);
+    assert_eq!(
+        report.plat_info,
+        PlatformInfo::SMT_EN | PlatformInfo::TSME_EN
+    );
+    assert_eq!(report.report_data, [0xAA; 64]);
+    assert_eq!(report.measurement, [0xBB; 48]);
+    assert_eq!(report.reported_tcb.snp, 8);
+    assert_eq!(report.chip_id, [0xCC; 64]);
+    assert_eq!(report.current_build, 5);
+    assert_eq!(report.current_minor, 52);
+    assert_eq!(report.current_major, 1);
+    assert_eq!(report.signature.r[0], 0x11);
+    assert_eq!(report.signature.s[0], 0x22);
+    assert_eq!(report.signing_key(), Some(SigningKey::Vcek));
+
+    let mut encoded = vec![];
+    report.encode(&mut encoded, ()).unwrap();
+    assert_eq!(encoded, bytes);
+
+    bytes[0x48] = 1 << 2; // key_info: signed with the VLEK
+    let report = AttestationReport::decode(&bytes[..], ()).unwrap();
+    assert_eq!(report.signing_key(), Some(SigningKey::Vlek));
+
+    bytes[0x48] = 7 << 2; // key_info: not signed
+    let report = AttestationReport::decode(&bytes[..], ()).unwrap();
+    assert_eq!(report.signing_key(), Some(SigningKey::Unsigned));
+
+    bytes[0x48] = 3 << 2; // key_info: reserved
+    let report = AttestationReport::decode(&bytes[..], ()).unwrap();
+    assert_eq!(report.signing_key(), None);
+}
+
+#[test]
+fn cert_table_codec() {
+    let table = CertTable::new(vec![
+        CertTableEntry::new(CertType::Vcek, vec![0xAA; 3]),
+        CertTableEntry::new(CertType::Ask, vec![0xBB; 2]),
+        CertTableEntry::new(CertType::Other([0x11; 16]), vec![0xCC]),
+    ]);
+
+    let mut bytes = vec![];
+    table.encode(&mut bytes, ()).unwrap();
+    assert_eq!(bytes.len(), 4 * 24 + 6);
+    assert_eq!(bytes[..4], [0x63, 0xda, 0x75, 0x8d]);
+    assert_eq!(bytes[16..24], [96, 0, 0, 0, 3, 0, 0, 0]);
+    assert_eq!(bytes[40..48], [99, 0, 0, 0, 2, 0, 0, 0]);
+    assert_eq!(bytes[72..96], [0; 24]);
+    assert_eq!(bytes[96..], [0xAA, 0xAA, 0xAA, 0xBB, 0xBB, 0xCC]);
+
+    // The table is usually returned padded to whole pages.
+    bytes.resize(4096, 0);
+    let decoded = CertTable::decode(&bytes[..], ()).unwrap();
+    assert_eq!(decoded, table);
+    assert_eq!(decoded.get(CertType::Ask), Some(&[0xBB, 0xBB][..]));
+    assert_eq!(decoded.get(CertType::Ark), None);
+
+    assert_eq!(
+        CertTable::from_bytes(&[0; 4096]).unwrap(),
+        CertTable::default()
+    );
+    assert!(CertTable::from_bytes(&bytes[..48]).is_err());
+
+    bytes[23] = 0xFF; // VCEK length beyond the buffer
+    assert!(CertTable::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn secrets_page() {
+    assert_eq!(std::mem::size_of::<SecretsPage>(), 4096);
+
+    let mut bytes = vec![0u8; 4096];
+    bytes[0x00..0x04].copy_from_slice(&3u32.to_le_bytes());
+    bytes[0x04] = 1;
+    bytes[0x08..0x0c].copy_from_slice(&0x00a0_0f11u32.to_le_bytes());
+    for vmpl in 0..4 {
+        let offset = 0x20 + vmpl * VMPCK_LEN;
+        bytes[offset..offset + VMPCK_LEN].copy_from_slice(&[vmpl as u8 + 1; VMPCK_LEN]);
+    }
+    bytes[0xa0] = 0xaa;
+    bytes[0x160..0x164].copy_from_slice(&5u32.to_le_bytes());
+
+    let page = SecretsPage::decode(&bytes[..], ()).unwrap();
+    assert_eq!(page.version, 3);
+    assert!(page.imi_en());
+    assert_eq!(page.fms, 0x00a0_0f11);
+    assert_eq!(page.vmpck(0), Some(&[1; VMPCK_LEN]));
+    assert_eq!(page.vmpck(3), Some(&[4; VMPCK_LEN]));
+    assert_eq!(page.vmpck(4), None);
+    assert_eq!(page.os_area[0], 0xaa);
+    assert_eq!(page.tsc_factor, 5);
+    assert!(!format!("{:?}", page).contains("[1, 1"));
+
+    assert!(SecretsPage::decode(&bytes[..4095], ()).is_err());
+}
+
+#[cfg_attr(not(has_sev_guest), ignore)]
+#[test]
+fn get_report() {
+    let mut fw = GuestFirmware::open().unwrap();
+    let report = fw.get_report([0xAAu8; 64], 0).unwrap();
+    assert_eq!(report.report_data, [0xAAu8; 64]);
+    assert_eq!(report.vmpl, 0);
+}
+
+#[cfg_attr(not(has_sev_guest), ignore)]
+#[test]
+fn get_derived_key() {
+    let mut fw = GuestFirmware::open().unwrap();
+    let request = DerivedKeyRequest {
+        root_key: RootKey::Vcek,
+        fields: GuestFieldSelect::MEASUREMENT | GuestFieldSelect::POLICY,
+        vmpl: 0,
+        guest_svn: 0,
+        tcb_version: TcbVersion::default(),
+    };
+
+    let a = fw.get_derived_key(&request).unwrap();
+    let b = fw.get_derived_key(&request).unwrap();
+    assert_eq!(a, b);
+}
+
+#[cfg_attr(not(has_sev_guest), ignore)]
+#[test]
+fn get_ext_report() {
+    let mut fw = GuestFirmware::open().unwrap();
+    let (report, certs) = fw.get_ext_report([0u8; 64], 0).unwrap();
+    assert_eq!(report.report_data, [0u8; 64]);
+    assert!(certs.get(CertType::Vcek).is_some() || certs.get(CertType::Vlek).is_some());
+}
+
+mod message {
+    use sev::firmware::guest::message::*;
+
+    use std::convert::TryFrom;
+
+    use codicon::{Decoder, Encoder};
+
+    #[test]
+    fn header_layout() {
+        assert_eq!(std::mem::size_of::<MessageHeader>(), 0x60);
+        assert_eq!(MAX_PAYLOAD_SIZE, 4000);
+
+        let header = MessageHeader::new(MessageType::ReportReq, 1, 7, 0, 96).unwrap();
+        let mut bytes = vec![];
+        header.encode(&mut bytes, ()).unwrap();
+        assert_eq!(&bytes[0x20..0x28], &7u64.to_le_bytes());
+        assert_eq!(bytes[0x30], 1);
+        assert_eq!(bytes[0x31], 1);
+        assert_eq!(&bytes[0x32..0x34], &0x60u16.to_le_bytes());
+        assert_eq!(bytes[0x34], 5);
+        assert_eq!(bytes[0x35], 1);
+        assert_eq!(&bytes[0x36..0x38], &96u16.to_le_bytes());
+        assert_eq!(bytes[0x3c], 0);
+        assert_eq!(MessageHeader::decode(&bytes[..], ()).unwrap(), header);
+
+        assert!(MessageHeader::new(MessageType::ReportReq, 1, 7, 0, 4001).is_err());
+    }
+
+    #[test]
+    fn message_type() {
+        assert_eq!(
+            MessageType::ReportReq.response(),
+            Some(MessageType::ReportRsp)
+        );
+        assert_eq!(
+            MessageType::TscInfoReq.response(),
+            Some(MessageType::TscInfoRsp)
+        );
+        assert_eq!(MessageType::KeyRsp.response(), None);
+        assert!(MessageType::try_from(0).is_err());
+        assert!(MessageType::try_from(19).is_err());
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn exchange() {
+        let vmpck = [0x42; 32];
+
+        // The guest requests a report...
+        let request = Message::seal(&vmpck, MessageType::ReportReq, 1, 1, 0, &[0u8; 96]).unwrap();
+        let mut page = vec![0u8; MAX_MESSAGE_SIZE];
+        request.encode(&mut page[..], ()).unwrap();
+
+        // ...which a stand-in for the AMD SP answers.
+        let received = Message::decode(&page[..], ()).unwrap();
+        assert_eq!(received, request);
+        assert_eq!(received.open(&vmpck).unwrap(), vec![0u8; 96]);
+
+        let kind = received.header.message_type().unwrap().response().unwrap();
+        let seqno = received.header.msg_seqno + 1;
+        let response = Message::seal(&vmpck, kind, 1, seqno, 0, b"report").unwrap();
+        assert_ne!(&response.payload[..], b"report");
+
+        assert_eq!(
+            response.header.message_type().unwrap(),
+            MessageType::ReportRsp
+        );
+        assert_eq!(response.header.msg_seqno, 2);
+        assert_eq!(response.open(&vmpck).unwrap(), b"report");
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn tampered() {
+        let vmpck = [0x42; 32];
+        let message = Message::seal(&vmpck, MessageType::KeyReq, 1, 3, 0, b"key").unwrap();
+
+        assert!(message.open(&[0x24; 32]).is_err());
+
+        let mut seqno = message.clone();
+        seqno.header.msg_seqno += 2;
+        assert!(seqno.open(&vmpck).is_err());
+
+        let mut kind = message.clone();
+        kind.header.msg_type = MessageType::ReportReq as u8;
+        assert!(kind.open(&vmpck).is_err());
+
+        let mut payload = message;
+        payload.payload[0] ^= 1;
+        assert!(payload.open(&vmpck).is_err());
+    }
+}
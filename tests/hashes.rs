@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "openssl")]
+
+use sev::launch::hashes::HashTable;
+use sev::launch::sev::{LaunchDigest, Policy};
+
+use openssl::sha::sha256;
+
+#[test]
+fn layout() {
+    let table = HashTable::new(&[0xf4; 1000], &[], "console=ttyS0");
+    let bytes = table.as_bytes();
+
+    assert_eq!(HashTable::LEN, 176);
+    assert_eq!(bytes.len(), HashTable::LEN);
+    assert_eq!(&bytes[16..18], &168u16.to_le_bytes());
+    assert_eq!(&bytes[34..36], &50u16.to_le_bytes());
+    assert_eq!(&bytes[36..68], &sha256(b"console=ttyS0\0"));
+    assert_eq!(&bytes[86..118], &sha256(b""));
+    assert_eq!(&bytes[136..168], &sha256(&[0xf4; 1000]));
+    assert_eq!(&bytes[168..], &[0u8; 8]);
+
+    assert_eq!(
+        sha256(bytes),
+        [
+            0x19, 0xa3, 0xdd, 0x17, 0x50, 0x15, 0x2b, 0x63, 0x4e, 0x09, 0x4c, 0xf6, 0x0b, 0x0a,
+            0x46, 0x64, 0x41, 0x56, 0x5a, 0x48, 0x4b, 0xa6, 0x0a, 0x55, 0xce, 0x35, 0x3c, 0x36,
+            0xa2, 0x74, 0x94, 0xd5,
+        ]
+    );
+}
+
+#[test]
+fn from_digests() {
+    let table = HashTable::new(b"kernel", b"initrd", "");
+    let digests = HashTable::from_digests(sha256(b"kernel"), sha256(b"initrd"), sha256(b"\0"));
+    assert_eq!(table, digests);
+}
+
+#[test]
+fn page() {
+    let table = HashTable::new(b"kernel", b"initrd", "");
+
+    let page = table.page(0xc00).unwrap();
+    assert_eq!(page.len(), 4096);
+    assert_eq!(&page[0xc00..0xc00 + HashTable::LEN], table.as_bytes());
+    assert!(page[..0xc00].iter().all(|b| *b == 0));
+
+    assert!(table.page(4096 - HashTable::LEN).is_some());
+    assert!(table.page(4096 - HashTable::LEN + 1).is_none());
+    assert!(table.page(usize::MAX).is_none());
+}
+
+#[test]
+fn measure() {
+    let table = HashTable::new(b"kernel", b"initrd", "");
+
+    let mut digest = LaunchDigest::new(Policy::default()).unwrap();
+    digest.update_data(&[0u8; 4096]).unwrap();
+    digest.update_data(table.as_bytes()).unwrap();
+
+    let expected = sha256(&[&[0u8; 4096][..], table.as_bytes()].concat());
+    assert_eq!(digest.digest().unwrap(), expected);
+}
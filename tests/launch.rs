@@ -63,7 +63,8 @@ fn sev() {
         let launcher = Launcher::new(&mut vm, &mut sev).unwrap();
         let mut launcher = launcher.start(start).unwrap();
         launcher.update_data(address_space.as_ref()).unwrap();
-        let launcher = launcher.measure().unwrap();
+        let mut launcher = launcher.measure().unwrap();
+        assert_eq!(launcher.status().unwrap().state, GuestState::LaunchSecret);
         let measurement = launcher.measurement();
         (launcher, measurement)
     };
@@ -73,7 +74,20 @@ fn sev() {
 
     launcher.inject(&secret, address_space.addr()).unwrap();
 
-    let _handle = launcher.finish().unwrap();
+    let handle = launcher.finish().unwrap();
+    let status = handle.status(&mut vm, &mut sev).unwrap();
+    assert_eq!(status.handle, handle);
+    assert_eq!(status.policy, policy);
+    assert_eq!(status.state, GuestState::Running);
+
+    let report = handle.report(&mut vm, &mut sev, [0x42; 16]).unwrap();
+    assert_eq!(report.mnonce, [0x42; 16]);
+    assert_eq!(report.policy, policy);
+
+    let mut code = [0u8; 16];
+    let mut debugger = GuestMemoryDebugger::new(&mut vm, &mut sev).unwrap();
+    debugger.read(address_space.addr(), &mut code).unwrap();
+    assert_eq!(&code, CODE);
 
     let vcpu = vm.create_vcpu(0).unwrap();
     let mut sregs = vcpu.get_sregs().unwrap();
@@ -93,3 +107,81 @@ fn sev() {
         }
     }
 }
+
+#[cfg_attr(not(has_sev), ignore)]
+#[test]
+#[serial]
+fn sev_es() {
+    let mut sev = Firmware::open().unwrap();
+    let chain = cached_chain::get().expect(
+        r#"could not find certificate chain
+        export with: sevctl export --full ~/.cache/amd-sev/chain"#,
+    );
+
+    let policy = Policy {
+        flags: PolicyFlags::ENCRYPTED_STATE,
+        ..Default::default()
+    };
+    let session = Session::try_from(policy).unwrap();
+    let start = session.start(chain).unwrap();
+
+    let kvm = Kvm::new().unwrap();
+    let mut vm = kvm.create_vm().unwrap();
+
+    const MEM_SIZE: usize = 0x1000;
+    let mut address_space = Map::map(MEM_SIZE)
+        .anywhere()
+        .anonymously()
+        .known::<perms::ReadWrite>(Kind::Private)
+        .unwrap();
+
+    address_space[..CODE.len()].copy_from_slice(&CODE[..]);
+
+    let mem_region = kvm_userspace_memory_region {
+        slot: 0,
+        guest_phys_addr: 0,
+        memory_size: address_space.size() as _,
+        userspace_addr: address_space.addr() as _,
+        flags: 0,
+    };
+
+    unsafe {
+        vm.set_user_memory_region(mem_region).unwrap();
+    }
+
+    let (vcpu, measurement) = {
+        let launcher = Launcher::new_es(&mut vm, &mut sev).unwrap();
+        let mut launcher = launcher.start(start).unwrap();
+        launcher.update_data(address_space.as_ref()).unwrap();
+
+        // The register state must be set before the VMSA is encrypted.
+        let vcpu = launcher.as_mut_vmfd().create_vcpu(0).unwrap();
+        let mut sregs = vcpu.get_sregs().unwrap();
+        sregs.cs.base = 0;
+        sregs.cs.selector = 0;
+        vcpu.set_sregs(&sregs).unwrap();
+
+        let mut regs = vcpu.get_regs().unwrap();
+        regs.rip = 0;
+        regs.rflags = 2;
+        vcpu.set_regs(&regs).unwrap();
+
+        launcher.update_vmsa().unwrap();
+
+        let launcher = launcher.measure().unwrap();
+        let measurement = launcher.measurement();
+        launcher.finish().unwrap();
+        (vcpu, measurement)
+    };
+
+    // The VMSA contents are generated by KVM, so the measurement cannot be
+    // predicted here.
+    let _session = unsafe { session.mock_verify(measurement) }.unwrap();
+
+    loop {
+        match vcpu.run().unwrap() {
+            VcpuExit::Hlt => break,
+            exit_reason => panic!("unexpected exit reason: {:?}", exit_reason),
+        }
+    }
+}
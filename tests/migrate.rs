@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use sev::launch::migrate::*;
+use sev::launch::sev::*;
+
+use codicon::{Decoder, Encoder};
+
+use std::fs::File;
+
+#[test]
+fn no_send() {
+    let mut vm = File::open("/dev/null").unwrap();
+    let mut sev = File::open("/dev/null").unwrap();
+
+    let policy = Policy {
+        flags: PolicyFlags::NO_SEND,
+        ..Default::default()
+    };
+
+    let err = Sender::new(&mut vm, &mut sev, policy).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    assert!(Sender::new(&mut vm, &mut sev, Policy::default()).is_ok());
+}
+
+#[test]
+fn packet_codec() {
+    let packet = Packet {
+        header: Header {
+            flags: HeaderFlags::default(),
+            iv: [0x11; 16],
+            mac: [0x22; 32],
+        },
+        ciphertext: vec![0x33; 32],
+    };
+
+    let mut encoded = vec![];
+    packet.encode(&mut encoded, ()).unwrap();
+    assert_eq!(encoded.len(), 4 + 16 + 32 + 32);
+    assert_eq!(&encoded[4..20], &[0x11; 16]);
+
+    assert_eq!(Packet::decode(&encoded[..], ()).unwrap(), packet);
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use sev::launch::ovmf::*;
+use sev::launch::snp::PageType;
+
+const IMAGE_SIZE: usize = 64 * 1024;
+const METADATA_OFFSET: usize = 0x1000;
+
+fn guid(s: &str) -> [u8; 16] {
+    let hex: Vec<u8> = s
+        .split('-')
+        .collect::<String>()
+        .as_bytes()
+        .chunks(2)
+        .map(|c| u8::from_str_radix(std::str::from_utf8(c).unwrap(), 16).unwrap())
+        .collect();
+
+    let mut guid = [0u8; 16];
+    guid.copy_from_slice(&hex);
+    guid[0..4].reverse();
+    guid[4..6].reverse();
+    guid[6..8].reverse();
+    guid
+}
+
+fn entry(guid: &str, data: &[u8]) -> Vec<u8> {
+    let mut entry = data.to_vec();
+    entry.extend_from_slice(&(data.len() as u16 + 18).to_le_bytes());
+    entry.extend_from_slice(&self::guid(guid));
+    entry
+}
+
+fn words(words: &[u32]) -> Vec<u8> {
+    words
+        .iter()
+        .flat_map(|w| w.to_le_bytes().to_vec())
+        .collect()
+}
+
+fn image(snp: bool) -> Vec<u8> {
+    let mut image = vec![0u8; IMAGE_SIZE];
+
+    let mut table = vec![];
+    table.extend(entry(
+        "00f771de-1a7e-4fcb-890e-68c77e2fb44e",
+        &words(&[0x80b004]),
+    ));
+    table.extend(entry(
+        "7255371f-3a3b-4b04-927b-1da6efa8d454",
+        &words(&[0x80c000, 0x400]),
+    ));
+    table.extend(entry(
+        "4c2eb361-7d9b-4cc3-8081-127c90d3d294",
+        &words(&[0x80d000, 0x1000]),
+    ));
+
+    if snp {
+        table.extend(entry(
+            "dc886566-984a-4798-a75e-5585a7bf67cc",
+            &words(&[METADATA_OFFSET as u32]),
+        ));
+
+        let mut metadata = b"ASEV".to_vec();
+        metadata.extend(words(&[16 + 4 * 12, 1, 4]));
+        metadata.extend(words(&[0x800000, 0x9000, 1]));
+        metadata.extend(words(&[0x80d000, 0x1000, 2]));
+        metadata.extend(words(&[0x80e000, 0x1000, 3]));
+        metadata.extend(words(&[0x80c000, 0x1000, 0x10]));
+
+        let at = IMAGE_SIZE - METADATA_OFFSET;
+        image[at..at + metadata.len()].copy_from_slice(&metadata);
+    }
+
+    let mut footer = ((table.len() + 18) as u16).to_le_bytes().to_vec();
+    footer.extend_from_slice(&guid("96b582de-1fb2-45f7-baea-a366c55a082d"));
+    table.extend(footer);
+
+    let end = IMAGE_SIZE - 32;
+    image[end - table.len()..end].copy_from_slice(&table);
+    image
+}
+
+#[test]
+fn guid_layout() {
+    assert_eq!(
+        guid("96b582de-1fb2-45f7-baea-a366c55a082d"),
+        [
+            0xde, 0x82, 0xb5, 0x96, 0xb2, 0x1f, 0xf7, 0x45, 0xba, 0xea, 0xa3, 0x66, 0xc5, 0x5a,
+            0x08, 0x2d,
+        ]
+    );
+}
+
+#[test]
+fn sev() {
+    let image = image(false);
+    let ovmf = Ovmf::new(&image).unwrap();
+
+    assert_eq!(ovmf.gpa(), 0x1_0000_0000 - IMAGE_SIZE as u64);
+    assert_eq!(ovmf.sev_es_reset_eip().unwrap(), Some(0x80b004));
+    assert_eq!(
+        ovmf.sev_hash_table().unwrap(),
+        Some(Region {
+            gpa: 0x80c000,
+            size: 0x400
+        })
+    );
+    assert_eq!(
+        ovmf.sev_secret().unwrap(),
+        Some(Region {
+            gpa: 0x80d000,
+            size: 0x1000
+        })
+    );
+    assert!(ovmf.sections().is_empty());
+    assert!(ovmf.snp_updates(false, |_, _| &[]).is_err());
+
+    let regions = ovmf.sev_regions(true).unwrap();
+    assert_eq!(regions.len(), 2);
+    assert_eq!(regions[0].gpa as u64, ovmf.gpa());
+    assert_eq!(regions[0].size as usize, IMAGE_SIZE);
+    assert_eq!(
+        regions[1],
+        Region {
+            gpa: 0x80c000,
+            size: 176
+        }
+    );
+}
+
+#[test]
+fn snp() {
+    let image = image(true);
+    let ovmf = Ovmf::new(&image).unwrap();
+
+    let types: Vec<_> = ovmf.sections().iter().map(|s| s.section_type).collect();
+    assert_eq!(
+        types,
+        [
+            SectionType::SnpSecMemory,
+            SectionType::SnpSecrets,
+            SectionType::Cpuid,
+            SectionType::SnpKernelHashes
+        ]
+    );
+
+    let zero = vec![0u8; 0x9000];
+    let updates = ovmf
+        .snp_updates(false, |gpa, len| {
+            if gpa == ovmf.gpa() {
+                &image[..len]
+            } else {
+                &zero[..len]
+            }
+        })
+        .unwrap();
+    assert_eq!(updates.len(), 5);
+
+    let sections = ovmf.sections();
+    assert_eq!(sections[0].page_type(false), PageType::Zero);
+    assert_eq!(sections[1].page_type(false), PageType::Secrets);
+    assert_eq!(sections[2].page_type(false), PageType::Cpuid);
+    assert_eq!(sections[3].page_type(false), PageType::Zero);
+    assert_eq!(sections[3].page_type(true), PageType::Normal);
+}
+
+#[test]
+fn no_table() {
+    let image = vec![0u8; IMAGE_SIZE];
+    let ovmf = Ovmf::new(&image).unwrap();
+    assert_eq!(ovmf.sev_es_reset_eip().unwrap(), None);
+    assert_eq!(ovmf.sev_hash_table().unwrap(), None);
+    assert!(ovmf.sections().is_empty());
+}
+
+#[test]
+fn invalid() {
+    assert!(Ovmf::new(&[0u8; 100]).is_err());
+
+    let mut image = image(true);
+    let at = IMAGE_SIZE - METADATA_OFFSET;
+    image[at] = b'X';
+    assert!(Ovmf::new(&image).is_err());
+
+    let mut image = self::image(true);
+    let at = IMAGE_SIZE - METADATA_OFFSET + 12;
+    image[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(Ovmf::new(&image).is_err());
+
+    let mut image = self::image(false);
+    let at = IMAGE_SIZE - 32 - 18;
+    image[at] = 0xff;
+    image[at + 1] = 0xff;
+    assert!(Ovmf::new(&image).is_err());
+}
@@ -36,3 +36,55 @@ mod initialized {
             .unwrap();
     }
 }
+
+mod transport {
+    use ::sev::{certs::builtin::naples::*, certs::*, launch, session::Session};
+    use codicon::Decoder;
+    use std::convert::*;
+
+    #[test]
+    fn start() {
+        const CEK: &[u8] = include_bytes!("naples/cek.cert");
+        const OCA: &[u8] = include_bytes!("naples/oca.cert");
+        const PEK: &[u8] = include_bytes!("naples/pek.cert");
+        const PDH: &[u8] = include_bytes!("naples/pdh.cert");
+
+        let policy = launch::sev::Policy::default();
+        let session = Session::try_from(policy).unwrap().transport();
+        let start = session
+            .start(Chain {
+                ca: ca::Chain {
+                    ark: ca::Certificate::decode(&mut &ARK[..], ()).unwrap(),
+                    ask: ca::Certificate::decode(&mut &ASK[..], ()).unwrap(),
+                },
+                sev: sev::Chain {
+                    cek: sev::Certificate::decode(&mut &CEK[..], ()).unwrap(),
+                    oca: sev::Certificate::decode(&mut &OCA[..], ()).unwrap(),
+                    pek: sev::Certificate::decode(&mut &PEK[..], ()).unwrap(),
+                    pdh: sev::Certificate::decode(&mut &PDH[..], ()).unwrap(),
+                },
+            })
+            .unwrap();
+
+        assert_eq!(start.policy, policy);
+        assert_eq!(Usage::try_from(&start.cert).unwrap(), Usage::PDH);
+    }
+
+    #[test]
+    fn packet() {
+        let session = Session::try_from(launch::sev::Policy::default())
+            .unwrap()
+            .transport();
+
+        let data = [0xA5u8; 64];
+        let mut packet = session
+            .packet(launch::sev::HeaderFlags::default(), &data)
+            .unwrap();
+        assert_ne!(&packet.ciphertext[..], &data[..]);
+        assert_eq!(session.open(&packet).unwrap(), &data[..]);
+
+        packet.ciphertext[0] ^= 1;
+        assert!(session.verify(&packet).is_err());
+        assert!(session.open(&packet).is_err());
+    }
+}
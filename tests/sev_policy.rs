@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use sev::firmware::{PlatformStatusFlags, State, Status};
+use sev::launch::sev::*;
+use sev::{Build, Version};
+
+use std::convert::TryFrom;
+
+fn status(flags: PlatformStatusFlags, major: u8, minor: u8) -> Status {
+    Status {
+        build: Build {
+            version: Version { major, minor },
+            build: 0,
+        },
+        state: State::Initialized,
+        flags,
+        guests: 0,
+    }
+}
+
+#[test]
+fn encode() {
+    let policy = Policy {
+        flags: PolicyFlags::NO_DEBUG | PolicyFlags::ENCRYPTED_STATE,
+        minfw: Version {
+            major: 0,
+            minor: 24,
+        },
+    };
+
+    assert_eq!(u32::from(policy), 0x1800_0005);
+    assert_eq!(u32::from(Policy::default()), 0);
+}
+
+#[test]
+fn decode() {
+    let policy = Policy::try_from(0x1800_0005).unwrap();
+    assert_eq!(
+        policy.flags,
+        PolicyFlags::NO_DEBUG | PolicyFlags::ENCRYPTED_STATE
+    );
+    assert_eq!(
+        policy.minfw,
+        Version {
+            major: 0,
+            minor: 24
+        }
+    );
+}
+
+#[test]
+fn roundtrip() {
+    for raw in &[0u32, 0x0000_0001, 0x0000_003f, 0x1800_0005, 0x1101_0022] {
+        assert_eq!(u32::from(Policy::try_from(*raw).unwrap()), *raw);
+    }
+}
+
+#[test]
+fn reserved() {
+    for raw in &[0x0000_0040u32, 0x0000_8000, 0x1800_0105] {
+        assert!(Policy::try_from(*raw).is_err());
+    }
+
+    // Policies reported by the firmware keep the known flags.
+    let policy = Policy::from_bits_truncate(0x1800_0105);
+    assert_eq!(u32::from(policy), 0x1800_0005);
+}
+
+#[test]
+fn check() {
+    let es = Policy {
+        flags: PolicyFlags::ENCRYPTED_STATE,
+        minfw: Version {
+            major: 0,
+            minor: 17,
+        },
+    };
+
+    let platform = status(PlatformStatusFlags::ENCRYPTED_STATE, 0, 24);
+    assert_eq!(es.check(&platform), Ok(()));
+    assert_eq!(Policy::default().check(&platform), Ok(()));
+
+    let no_es = status(PlatformStatusFlags::empty(), 0, 24);
+    assert_eq!(
+        es.check(&no_es),
+        Err(PolicyError::EncryptedStateUnsupported)
+    );
+
+    let old = status(PlatformStatusFlags::ENCRYPTED_STATE, 0, 16);
+    assert_eq!(
+        es.check(&old),
+        Err(PolicyError::FirmwareTooOld {
+            required: es.minfw,
+            installed: old.build.version,
+        })
+    );
+}
@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "openssl")]
+
+use sev::certs::snp::{ca, Certificate, Chain};
+use sev::certs::Verifiable;
+use sev::firmware::guest::{AttestationReport, SigningKey};
+
+use codicon::Decoder;
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::sha::sha384;
+use openssl::x509::{X509Builder, X509NameBuilder};
+
+fn issue(
+    cn: &str,
+    key: &PKey<Private>,
+    issuer: Option<(&Certificate, &PKey<Private>)>,
+) -> Certificate {
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", cn).unwrap();
+    let name = name.build();
+
+    let mut builder = X509Builder::new().unwrap();
+    builder.set_version(2).unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_pubkey(key).unwrap();
+    builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+
+    match issuer {
+        Some((cert, signer)) => {
+            builder
+                .set_issuer_name(cert.as_ref().subject_name())
+                .unwrap();
+            builder.sign(signer, MessageDigest::sha384()).unwrap();
+        }
+        None => {
+            builder.set_issuer_name(&name).unwrap();
+            builder.sign(key, MessageDigest::sha384()).unwrap();
+        }
+    }
+
+    builder.build().into()
+}
+
+fn ec_key() -> PKey<Private> {
+    let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+    PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+}
+
+fn rsa_key() -> PKey<Private> {
+    PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap()
+}
+
+fn chain() -> (Chain, PKey<Private>) {
+    let ark_key = rsa_key();
+    let ask_key = rsa_key();
+    let vcek_key = ec_key();
+
+    let ark = issue("ARK-Test", &ark_key, None);
+    let ask = issue("SEV-Test", &ask_key, Some((&ark, &ark_key)));
+    let vcek = issue("SEV-VCEK", &vcek_key, Some((&ask, &ask_key)));
+
+    let chain = Chain {
+        ca: ca::Chain { ark, ask },
+        vcek,
+    };
+
+    (chain, vcek_key)
+}
+
+fn le(num: &BigNum) -> [u8; 72] {
+    let mut buf = [0u8; 72];
+    for (i, b) in num.to_vec().iter().rev().enumerate() {
+        buf[i] = *b;
+    }
+    buf
+}
+
+fn report(key: &PKey<Private>) -> Vec<u8> {
+    report_signed_by(key, SigningKey::Vcek)
+}
+
+fn report_signed_by(key: &PKey<Private>, signing_key: SigningKey) -> Vec<u8> {
+    let mut bytes = vec![0u8; 1184];
+    bytes[0x00] = 2; // version
+    bytes[0x34] = 1; // sig_algo
+    bytes[0x48] = match signing_key {
+        SigningKey::Vcek => 0,
+        SigningKey::Vlek => 1 << 2,
+        SigningKey::Unsigned => 7 << 2,
+    }; // key_info
+    bytes[0x50..0x90].copy_from_slice(&[0x5A; 64]); // report_data
+
+    let digest = sha384(&bytes[..0x2A0]);
+    let sig = EcdsaSig::sign(&digest, &key.ec_key().unwrap()).unwrap();
+    bytes[0x2A0..0x2E8].copy_from_slice(&le(&sig.r().to_owned().unwrap()));
+    bytes[0x2E8..0x330].copy_from_slice(&le(&sig.s().to_owned().unwrap()));
+    bytes
+}
+
+#[test]
+fn chain_verify() {
+    let (chain, _) = chain();
+    assert_eq!((&chain).verify().unwrap(), &chain.vcek);
+    assert_eq!((&chain.ca).verify().unwrap(), &chain.ca.ask);
+}
+
+#[test]
+fn chain_verify_wrong_root() {
+    let (mut chain, _) = chain();
+    let (other, _) = self::chain();
+    chain.ca.ark = other.ca.ark;
+    assert!((&chain).verify().is_err());
+}
+
+#[test]
+fn report_verify() {
+    let (chain, key) = chain();
+    let bytes = report(&key);
+    let report = AttestationReport::decode(&bytes[..], ()).unwrap();
+    (&chain, &report).verify().unwrap();
+}
+
+#[test]
+fn report_verify_vlek() {
+    let (chain, key) = chain();
+    let bytes = report_signed_by(&key, SigningKey::Vlek);
+    let report = AttestationReport::decode(&bytes[..], ()).unwrap();
+    assert_eq!(report.signing_key(), Some(SigningKey::Vlek));
+    assert!((&chain, &report).verify().is_err());
+}
+
+#[test]
+fn report_verify_tampered() {
+    let (chain, key) = chain();
+    let mut bytes = report(&key);
+    bytes[0x50] ^= 1;
+    let report = AttestationReport::decode(&bytes[..], ()).unwrap();
+    assert!((&chain, &report).verify().is_err());
+}
+
+#[test]
+fn report_verify_wrong_vcek() {
+    let (chain, _) = chain();
+    let bytes = report(&ec_key());
+    let report = AttestationReport::decode(&bytes[..], ()).unwrap();
+    assert!((&chain, &report).verify().is_err());
+}
+
+#[test]
+fn certificate_codec() {
+    let (chain, _) = chain();
+    let der = chain.vcek.to_der().unwrap();
+    let pem = chain.vcek.to_pem().unwrap();
+    assert_eq!(Certificate::from_der(&der).unwrap(), chain.vcek);
+    assert_eq!(Certificate::from_pem(&pem).unwrap(), chain.vcek);
+    assert_eq!(Certificate::decode(&der[..], ()).unwrap(), chain.vcek);
+}
+
+#[test]
+fn chain_codec() {
+    let (chain, _) = chain();
+    let ark = chain.ca.ark.to_pem().unwrap();
+    let ask = chain.ca.ask.to_pem().unwrap();
+    let vcek = chain.vcek.to_pem().unwrap();
+    assert_eq!(Chain::from_pem(&ark, &ask, &vcek).unwrap(), chain);
+
+    let bundle = [&ask[..], &ark[..]].concat();
+    assert_eq!(ca::Chain::from_pem_bundle(&bundle).unwrap(), chain.ca);
+    assert!(ca::Chain::from_pem_bundle(&ask).is_err());
+
+    let ark = chain.ca.ark.to_der().unwrap();
+    let ask = chain.ca.ask.to_der().unwrap();
+    let vcek = chain.vcek.to_der().unwrap();
+    assert_eq!(Chain::from_der(&ark, &ask, &vcek).unwrap(), chain);
+}
+
+#[test]
+fn product_from_vcek() {
+    use sev::{Product, UnknownProduct};
+    use std::convert::TryFrom;
+
+    let ask_key = rsa_key();
+    let ask = issue("SEV-Genoa", &ask_key, None);
+    let vcek = issue("SEV-VCEK", &ec_key(), Some((&ask, &ask_key)));
+    assert_eq!(Product::try_from(&vcek), Ok(Product::Genoa));
+
+    let asvk_key = rsa_key();
+    let asvk = issue("SEV-VLEK-Milan", &asvk_key, None);
+    let vlek = issue("SEV-VLEK", &ec_key(), Some((&asvk, &asvk_key)));
+    assert_eq!(Product::try_from(&vlek), Ok(Product::Milan));
+
+    let (chain, _) = chain();
+    assert_eq!(
+        Product::try_from(&chain.vcek),
+        Err(UnknownProduct("SEV-Test".to_string()))
+    );
+}
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "openssl")]
+
+use sev::launch::snp::*;
+use sev::Version;
+
+use std::convert::TryFrom;
+
+use codicon::Encoder;
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::ecdsa::EcdsaSig;
+use openssl::nid::Nid;
+use openssl::sha::sha384;
+
+fn key() -> EcKey<openssl::pkey::Private> {
+    let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+    EcKey::generate(&group).unwrap()
+}
+
+fn le(bytes: &[u8]) -> BigNum {
+    BigNum::from_slice(&bytes.iter().rev().cloned().collect::<Vec<_>>()).unwrap()
+}
+
+fn block() -> IdBlock {
+    let policy = Policy {
+        flags: PolicyFlags::SMT,
+        minfw: Version {
+            major: 1,
+            minor: 51,
+        },
+    };
+
+    IdBlock::new([0x11; 48], [0x22; 16], [0x33; 16], 7, policy)
+}
+
+#[test]
+fn layout() {
+    assert_eq!(std::mem::size_of::<IdBlock>(), 0x60);
+    assert_eq!(std::mem::size_of::<IdPublicKey>(), 0x404);
+    assert_eq!(std::mem::size_of::<IdAuth>(), 0x1000);
+
+    let mut bytes = vec![];
+    block().encode(&mut bytes, ()).unwrap();
+    assert_eq!(bytes.len(), 0x60);
+    assert_eq!(&bytes[0x00..0x30], &[0x11; 48][..]);
+    assert_eq!(&bytes[0x30..0x40], &[0x22; 16]);
+    assert_eq!(&bytes[0x40..0x50], &[0x33; 16]);
+    assert_eq!(&bytes[0x50..0x54], &1u32.to_le_bytes());
+    assert_eq!(&bytes[0x54..0x58], &7u32.to_le_bytes());
+    assert_eq!(&bytes[0x58..0x60], &0x3_0133u64.to_le_bytes());
+}
+
+#[test]
+fn sign() {
+    let id_key = key();
+    let signer = IdSigner::new(id_key.clone()).unwrap();
+    let auth = signer.sign(&block()).unwrap();
+
+    let mut bytes = vec![];
+    auth.encode(&mut bytes, ()).unwrap();
+    assert_eq!(&bytes[0x00..0x04], &1u32.to_le_bytes());
+    assert_eq!(&bytes[0x04..0x08], &0u32.to_le_bytes());
+    assert_eq!(&bytes[0x240..0x244], &2u32.to_le_bytes());
+    assert_eq!(&bytes[0x880..0x1000], &[0u8; 0x780][..]);
+
+    let mut block_bytes = vec![];
+    block().encode(&mut block_bytes, ()).unwrap();
+    let sig = EcdsaSig::from_private_components(le(&auth.id_block_sig.r), le(&auth.id_block_sig.s))
+        .unwrap();
+    assert!(sig.verify(&sha384(&block_bytes), &id_key).unwrap());
+
+    assert_eq!(
+        signer.id_key_digest().unwrap(),
+        sha384(&bytes[0x240..0x644])
+    );
+    assert_eq!(signer.author_key_digest().unwrap(), [0u8; 48]);
+}
+
+#[test]
+fn sign_with_author_key() {
+    let author_key = key();
+    let signer = IdSigner::new(key())
+        .unwrap()
+        .with_author_key(author_key.clone())
+        .unwrap();
+    let auth = signer.sign(&block()).unwrap();
+
+    let mut bytes = vec![];
+    auth.encode(&mut bytes, ()).unwrap();
+    assert_eq!(&bytes[0x04..0x08], &1u32.to_le_bytes());
+
+    let sig =
+        EcdsaSig::from_private_components(le(&auth.id_key_sig.r), le(&auth.id_key_sig.s)).unwrap();
+    assert!(sig
+        .verify(&sha384(&bytes[0x240..0x644]), &author_key)
+        .unwrap());
+
+    assert_eq!(
+        signer.author_key_digest().unwrap(),
+        sha384(&bytes[0x880..0xc84])
+    );
+    assert_eq!(
+        signer.author_key_digest().unwrap(),
+        IdPublicKey::try_from(&*author_key)
+            .unwrap()
+            .digest()
+            .unwrap()
+    );
+}
+
+#[test]
+fn wrong_curve() {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let p256 = EcKey::generate(&group).unwrap();
+
+    assert!(IdSigner::new(p256.clone()).is_err());
+    assert!(IdSigner::new(key()).unwrap().with_author_key(p256).is_err());
+}
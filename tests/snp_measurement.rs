@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "openssl")]
+
+use sev::launch::snp::*;
+
+use openssl::sha::sha384;
+
+const CODE: &[u8; 8192] = &[0xf4; 8192];
+const EMPTY: &[u8; 4096] = &[0; 4096];
+
+#[test]
+fn empty() {
+    assert_eq!(Measurement::new().digest(), [0u8; 48]);
+}
+
+/// A page type, GPA, contents (for normal pages) and the VMPL3, VMPL2 and
+/// VMPL1 permissions.
+type Page<'a> = (u8, u64, Option<&'a [u8]>, [u8; 3]);
+
+/// An independent implementation of the launch digest, following the
+/// PAGE_INFO layout of the SNP firmware specification the way
+/// sev-snp-measure's `GCTX` does.
+fn reference(pages: &[Page]) -> [u8; 48] {
+    let mut ld = [0u8; 48];
+
+    for (page_type, gpa, contents, perms) in pages.iter() {
+        let mut page_info = ld.to_vec();
+        page_info.extend_from_slice(&contents.map(sha384).unwrap_or([0u8; 48]));
+        page_info.extend_from_slice(&0x70u16.to_le_bytes());
+        page_info.extend_from_slice(&[*page_type, 0, perms[0], perms[1], perms[2], 0]);
+        page_info.extend_from_slice(&gpa.to_le_bytes());
+        assert_eq!(page_info.len(), 0x70);
+
+        ld = sha384(&page_info);
+    }
+
+    ld
+}
+
+/// The launch digest of: two normal pages of `hlt` at GPA 0x1000, a zero
+/// page at 0x3000, the secrets page at 0x4000 and the CPUID page at 0x5000
+/// with VMPL1 RWXX, VMPL2 RW and VMPL3 R permissions.
+#[test]
+fn golden() {
+    let dp = VmplPerms::empty();
+    let perms = (
+        VmplPerms::all(),
+        VmplPerms::READ | VmplPerms::WRITE,
+        VmplPerms::READ,
+    );
+
+    let mut measurement = Measurement::new();
+    let updates = [
+        Update::new(1, CODE, false, PageType::Normal, (dp, dp, dp)),
+        Update::new(3, EMPTY, false, PageType::Zero, (dp, dp, dp)),
+        Update::new(4, EMPTY, false, PageType::Secrets, (dp, dp, dp)),
+        Update::new(5, EMPTY, false, PageType::Cpuid, perms),
+    ];
+
+    for update in updates.iter() {
+        measurement.update(update).unwrap();
+    }
+
+    let reference = reference(&[
+        (1, 0x1000, Some(&CODE[..4096]), [0; 3]),
+        (1, 0x2000, Some(&CODE[4096..]), [0; 3]),
+        (3, 0x3000, None, [0; 3]),
+        (5, 0x4000, None, [0; 3]),
+        (6, 0x5000, None, [0x1, 0x3, 0xf]),
+    ]);
+
+    // Also computed with Python's hashlib over the same PAGE_INFO structures.
+    let expected = [
+        0x62, 0x66, 0x4a, 0xad, 0x34, 0xb9, 0x72, 0x59, 0x91, 0xb2, 0x5b, 0xa5, 0xe5, 0xc6, 0xa4,
+        0x39, 0x2a, 0x0c, 0x30, 0xe9, 0xea, 0x41, 0x02, 0x41, 0x44, 0x67, 0x21, 0xdd, 0xbf, 0x53,
+        0xa7, 0x54, 0xec, 0xbf, 0x6a, 0xfa, 0xae, 0x26, 0x56, 0x5f, 0x43, 0x7f, 0x68, 0x60, 0xe9,
+        0x52, 0xc0, 0xed,
+    ];
+
+    assert_eq!(reference, expected);
+    assert_eq!(measurement.digest(), expected);
+}
+
+#[test]
+fn pages_are_measured_individually() {
+    let dp = VmplPerms::empty();
+
+    let mut whole = Measurement::new();
+    whole
+        .update(&Update::new(1, CODE, false, PageType::Normal, (dp, dp, dp)))
+        .unwrap();
+
+    let mut split = Measurement::new();
+    for (i, page) in CODE.chunks(4096).enumerate() {
+        let update = Update::new(1 + i as u64, page, false, PageType::Normal, (dp, dp, dp));
+        split.update(&update).unwrap();
+    }
+
+    assert_eq!(whole, split);
+}
+
+#[test]
+fn partial_page() {
+    let dp = VmplPerms::empty();
+    let update = Update::new(1, &CODE[..100], false, PageType::Normal, (dp, dp, dp));
+    assert!(Measurement::new().update(&update).is_err());
+}
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use sev::launch::snp::*;
+use sev::Version;
+
+use std::convert::TryFrom;
+
+#[test]
+fn encode() {
+    let policy = Policy {
+        flags: PolicyFlags::SMT | PolicyFlags::DEBUG | PolicyFlags::PAGE_SWAP_DISABLE,
+        minfw: Version {
+            major: 1,
+            minor: 51,
+        },
+    };
+
+    assert_eq!(u64::from(policy), 0x020b_0133);
+    assert_eq!(u64::from(Policy::default()), 0x2_0000);
+}
+
+#[test]
+fn decode() {
+    let policy = Policy::try_from(0x03ff_0133).unwrap();
+    assert_eq!(
+        policy.minfw,
+        Version {
+            major: 1,
+            minor: 51
+        }
+    );
+    assert_eq!(policy.flags, PolicyFlags::all() - PolicyFlags::RESERVED_1);
+    assert_eq!(u64::from(policy), 0x03ff_0133);
+
+    assert_eq!(Policy::try_from(0x2_0000).unwrap(), Policy::default());
+}
+
+#[test]
+fn decode_reserved() {
+    // The reserved-must-be-one bit is clear.
+    assert!(Policy::try_from(0x1_0000).is_err());
+
+    // A reserved bit above PAGE_SWAP_DISABLE is set.
+    assert!(Policy::try_from(0x0402_0000).is_err());
+    assert!(Policy::try_from(0x8000_0000_0002_0000).is_err());
+}
+
+#[test]
+fn display() {
+    let policy = Policy {
+        flags: PolicyFlags::SMT | PolicyFlags::SINGLE_SOCKET,
+        minfw: Version {
+            major: 1,
+            minor: 55,
+        },
+    };
+
+    assert_eq!(
+        policy.to_string(),
+        "ABI >= 1.55, SMT allowed, single socket"
+    );
+    assert_eq!(Policy::default().to_string(), "ABI >= 0.0");
+}
@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use sev::launch::vmsa::*;
+
+use codicon::{Decoder, Encoder};
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[at..at + 8]);
+    u64::from_le_bytes(buf)
+}
+
+#[test]
+fn bsp() {
+    let mut bytes = vec![];
+    Vmsa::bsp(0x00a00f11, SevFeatures::SNP_ACTIVE)
+        .encode(&mut bytes, ())
+        .unwrap();
+    assert_eq!(bytes.len(), 4096);
+
+    // CS
+    assert_eq!(u16_at(&bytes, 0x10), 0xf000);
+    assert_eq!(u16_at(&bytes, 0x12), 0x9b);
+    assert_eq!(u32_at(&bytes, 0x14), 0xffff);
+    assert_eq!(u64_at(&bytes, 0x18), 0xffff0000);
+
+    // LDTR, TR
+    assert_eq!(u16_at(&bytes, 0x72), 0x82);
+    assert_eq!(u16_at(&bytes, 0x92), 0x83);
+
+    assert_eq!(u64_at(&bytes, 0xd0), 0x1000); // EFER
+    assert_eq!(u64_at(&bytes, 0x148), 0x40); // CR4
+    assert_eq!(u64_at(&bytes, 0x158), 0x10); // CR0
+    assert_eq!(u64_at(&bytes, 0x160), 0x400); // DR7
+    assert_eq!(u64_at(&bytes, 0x168), 0xffff0ff0); // DR6
+    assert_eq!(u64_at(&bytes, 0x170), 0x2); // RFLAGS
+    assert_eq!(u64_at(&bytes, 0x178), 0xfff0); // RIP
+    assert_eq!(u64_at(&bytes, 0x268), 0x7040600070406); // G_PAT
+    assert_eq!(u64_at(&bytes, 0x310), 0x00a00f11); // RDX
+    assert_eq!(u64_at(&bytes, 0x3b0), 0x1); // SEV_FEATURES
+    assert_eq!(u64_at(&bytes, 0x3e8), 0x1); // XCR0
+    assert_eq!(u32_at(&bytes, 0x408), 0x1f80); // MXCSR
+    assert_eq!(u16_at(&bytes, 0x410), 0x37f); // x87 FCW
+
+    assert!(bytes[0x670..].iter().all(|b| *b == 0));
+}
+
+#[test]
+fn ap() {
+    let vmsa = Vmsa::ap(0x80b004, 0x00a00f11, SevFeatures::empty());
+    assert_eq!(vmsa.cs.base, 0x800000);
+    assert_eq!(vmsa.rip, 0xb004);
+    assert_eq!(vmsa.rdx, 0x00a00f11);
+    assert_eq!(vmsa.sev_features, SevFeatures::empty());
+    assert_eq!(
+        Vmsa::bsp(0, SevFeatures::empty()).cs,
+        Vmsa::new(0xfffffff0, 0, SevFeatures::empty()).cs
+    );
+}
+
+#[test]
+fn codec() {
+    let mut vmsa = Vmsa::bsp(
+        0x00a00f11,
+        SevFeatures::SNP_ACTIVE | SevFeatures::DEBUG_SWAP,
+    );
+    vmsa.rdx = 0x00a10f00;
+
+    let mut bytes = vec![];
+    vmsa.encode(&mut bytes, ()).unwrap();
+    assert_eq!(u64_at(&bytes, 0x310), 0x00a10f00); // RDX
+    assert_eq!(u64_at(&bytes, 0x3b0), 0x21);
+
+    assert_eq!(Vmsa::decode(&bytes[..], ()).unwrap(), vmsa);
+}
+
+/// The application processor VMSA of sev-snp-measure (`build_save_area` in
+/// `vmsa.py`) for the QEMU VMM type, an EPYC-Milan vCPU (signature
+/// 0x00a00f11), the OVMF SEV-ES reset EIP 0x80b004 and SNP_ACTIVE.
+#[test]
+fn sev_snp_measure() {
+    fn segment(page: &mut [u8], at: usize, selector: u16, attrib: u16, base: u64) {
+        page[at..at + 2].copy_from_slice(&selector.to_le_bytes());
+        page[at + 2..at + 4].copy_from_slice(&attrib.to_le_bytes());
+        page[at + 4..at + 8].copy_from_slice(&0xffffu32.to_le_bytes());
+        page[at + 8..at + 16].copy_from_slice(&base.to_le_bytes());
+    }
+
+    fn u64_set(page: &mut [u8], at: usize, value: u64) {
+        page[at..at + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    let mut expected = vec![0u8; 4096];
+    for at in [0x00, 0x20, 0x30, 0x40, 0x50].iter() {
+        segment(&mut expected, *at, 0, 0x93, 0); // ES, SS, DS, FS, GS
+    }
+    segment(&mut expected, 0x10, 0xf000, 0x9b, 0x800000); // CS
+    segment(&mut expected, 0x60, 0, 0, 0); // GDTR
+    segment(&mut expected, 0x70, 0, 0x82, 0); // LDTR
+    segment(&mut expected, 0x80, 0, 0, 0); // IDTR
+    segment(&mut expected, 0x90, 0, 0x83, 0); // TR
+    u64_set(&mut expected, 0xd0, 0x1000); // EFER
+    u64_set(&mut expected, 0x148, 0x40); // CR4
+    u64_set(&mut expected, 0x158, 0x10); // CR0
+    u64_set(&mut expected, 0x160, 0x400); // DR7
+    u64_set(&mut expected, 0x168, 0xffff0ff0); // DR6
+    u64_set(&mut expected, 0x170, 0x2); // RFLAGS
+    u64_set(&mut expected, 0x178, 0xb004); // RIP
+    u64_set(&mut expected, 0x268, 0x7040600070406); // G_PAT
+    u64_set(&mut expected, 0x310, 0x00a00f11); // RDX
+    u64_set(&mut expected, 0x3b0, 0x1); // SEV_FEATURES
+    u64_set(&mut expected, 0x3e8, 0x1); // XCR0
+    expected[0x408..0x40c].copy_from_slice(&0x1f80u32.to_le_bytes()); // MXCSR
+    expected[0x410..0x412].copy_from_slice(&0x37fu16.to_le_bytes()); // x87 FCW
+
+    let mut bytes = vec![];
+    Vmsa::ap(0x80b004, 0x00a00f11, SevFeatures::SNP_ACTIVE)
+        .encode(&mut bytes, ())
+        .unwrap();
+    assert_eq!(bytes, expected);
+}